@@ -3,24 +3,154 @@ use common_utils::{
     ClientToServerMessage, HostCommand, HostMessage, MessageBody, MessageContent,
     ServerToClientMessage,
 };
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use log::{info, warn};
 use rand::distr::Alphanumeric;
 use rand::prelude::SliceRandom;
 use rand::{rng, Rng};
+use std::fmt;
 use wg_2024::network::NodeId;
 use wg_2024::packet::NodeType;
 
+/// Results kept for a consumer that did not read them yet
+pub(crate) const COMMAND_RESULTS_CAPACITY: usize = 256;
+
+/// Why a command of the simulation controller was not executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// No other client is known, so there is no recipient for a random message
+    NoKnownClient,
+    /// The node to remove is not a neighbor of the client
+    UnknownSender(NodeId),
+    /// The command is not supported by this client, with its debug representation
+    Unsupported(String),
+    /// A session was given up before all its fragments were acknowledged
+    SessionFailed {
+        session_id: u64,
+        destination: NodeId,
+        reason: String,
+    },
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NoKnownClient => write!(f, "no other client is known"),
+            CommandError::UnknownSender(node_id) => write!(f, "{node_id} is not a neighbor"),
+            CommandError::Unsupported(command) => write!(f, "unsupported command {command}"),
+            CommandError::SessionFailed {
+                session_id,
+                destination,
+                reason,
+            } => write!(f, "session {session_id} to {destination} failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Outcome of a command of the simulation controller, or of a message sent
+/// by the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandResult {
+    pub client_id: NodeId,
+    /// Position of the result among the ones reported by the client, from 1
+    pub seq: u64,
+    /// Name of the command, e.g. `SendRandomMessage`, or `Session` for a
+    /// session that failed
+    pub command: &'static str,
+    pub outcome: Result<(), CommandError>,
+}
+
+/// Name of a command, as reported in its result
+fn command_name(command: &HostCommand) -> &'static str {
+    match command {
+        HostCommand::SendRandomMessage(_) => "SendRandomMessage",
+        HostCommand::DiscoverNetwork => "DiscoverNetwork",
+        HostCommand::AddSender(..) => "AddSender",
+        HostCommand::RemoveSender(_) => "RemoveSender",
+        HostCommand::Stop => "Stop",
+        _ => "Unknown",
+    }
+}
+
 impl RustbustersClient {
-    /// Handles various commands received from the controller
+    /// Takes the channel on which the result of every command is reported.
+    /// Until it is taken, only the most recent results are kept.
+    ///
+    /// The results do not reach the SC through its event channel, as no
+    /// `HostEvent` carries them: the SC has to read them from here.
+    ///
+    /// Returns None if it was already taken.
+    pub fn take_command_results(&mut self) -> Option<Receiver<CommandResult>> {
+        self.command_results_receiver.take()
+    }
+
+    /// Executes a command received from the controller and reports its result
     ///
     /// ### Arguments
     /// * `command` - The command to be executed
     /// * `ws_to_ui_sender` - Channel sender for sending messages back to the UI
+    ///
+    /// Returns the outcome of the command
     pub(crate) fn handle_command(
         &mut self,
         command: HostCommand,
         ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) -> Result<(), CommandError> {
+        let name = command_name(&command);
+        let outcome = self.execute_command(command, ws_to_ui_sender);
+        self.report_command_result(name, outcome.clone());
+        outcome
+    }
+
+    /// Reports the outcome of a command to whoever took the results channel
+    ///
+    /// ### Arguments
+    /// * `command` - The name of the command
+    /// * `outcome` - The outcome of the command
+    pub(crate) fn report_command_result(
+        &mut self,
+        command: &'static str,
+        outcome: Result<(), CommandError>,
     ) {
+        self.results_reported += 1;
+        match &outcome {
+            Ok(()) => info!("Client {}: Command {} executed", self.id, command),
+            Err(err) => warn!("Client {}: Command {} failed: {}", self.id, command, err),
+        }
+
+        let result = CommandResult {
+            client_id: self.id,
+            seq: self.results_reported,
+            command,
+            outcome,
+        };
+        if let Err(TrySendError::Full(result)) = self.command_results.try_send(result) {
+            match &self.command_results_receiver {
+                // Nobody took the channel yet: make room by dropping the oldest result
+                Some(receiver) => {
+                    if let Ok(oldest) = receiver.try_recv() {
+                        warn!(
+                            "Client {}: Dropping result {} of command {}, nobody took the results",
+                            self.id, oldest.seq, oldest.command
+                        );
+                    }
+                    let _ = self.command_results.try_send(result);
+                }
+                None => warn!(
+                    "Client {}: Dropping result {} of command {}, nobody reads them",
+                    self.id, result.seq, result.command
+                ),
+            }
+        }
+    }
+
+    fn execute_command(
+        &mut self,
+        command: HostCommand,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) -> Result<(), CommandError> {
         match command {
             HostCommand::SendRandomMessage(dest) => {
                 let random_client_dest = {
@@ -32,28 +162,30 @@ impl RustbustersClient {
                         })
                         .collect();
                     client_nodes.shuffle(&mut rng());
-                    client_nodes.first().map(|(node_id, _)| **node_id).unwrap()
+                    client_nodes
+                        .first()
+                        .map(|(node_id, _)| **node_id)
+                        .ok_or(CommandError::NoKnownClient)?
+                };
+
+                let message = ClientToServerMessage::SendPrivateMessage {
+                    recipient_id: random_client_dest,
+                    message: MessageBody {
+                        sender_id: self.id,
+                        timestamp: chrono::Local::now().format("%H:%M").to_string(),
+                        content: MessageContent::Text({
+                            let mut rng = rng();
+                            let length = rng.random_range(5..=20);
+                            let random_string: String = (0..length)
+                                .map(|_| rng.sample(Alphanumeric) as char)
+                                .collect();
+                            random_string
+                        }),
+                    },
                 };
+                self.record_sent_message(dest, &message);
 
-                self.send_message(
-                    dest,
-                    HostMessage::FromClient(ClientToServerMessage::SendPrivateMessage {
-                        recipient_id: random_client_dest,
-                        message: MessageBody {
-                            sender_id: self.id,
-                            timestamp: chrono::Local::now().format("%H:%M").to_string(),
-                            content: MessageContent::Text({
-                                let mut rng = rng();
-                                let length = rng.random_range(5..=20);
-                                let random_string: String = (0..length)
-                                    .map(|_| rng.sample(Alphanumeric) as char)
-                                    .collect();
-                                random_string
-                            }),
-                        },
-                    }),
-                    ws_to_ui_sender,
-                );
+                self.send_message(dest, HostMessage::FromClient(message), ws_to_ui_sender);
             }
             HostCommand::DiscoverNetwork => {
                 self.discover_network();
@@ -63,14 +195,17 @@ impl RustbustersClient {
                 self.discover_network();
             }
             HostCommand::RemoveSender(sender_id) => {
-                self.packet_send.remove(&sender_id);
+                if self.packet_send.remove(&sender_id).is_none() {
+                    return Err(CommandError::UnknownSender(sender_id));
+                }
                 self.topology.remove_edge(self.id, sender_id);
                 self.edge_stats.remove(&(self.id, sender_id));
+                self.topology_changed("sender removed");
                 self.discover_network();
             }
-            _ => {
-                unreachable!("Client {}: Unhandled command: {:?}", self.id, command);
-            }
+            // Stop is handled by the run loop, which owns the running flag
+            command => return Err(CommandError::Unsupported(format!("{command:?}"))),
         }
+        Ok(())
     }
 }
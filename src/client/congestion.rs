@@ -0,0 +1,205 @@
+use crate::client::RustbustersClient;
+use log::{debug, info};
+use std::collections::VecDeque;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+const DEFAULT_INITIAL_WINDOW: f32 = 4.0;
+const DEFAULT_MIN_WINDOW: f32 = 1.0;
+const DEFAULT_MAX_WINDOW: f32 = 64.0;
+const DEFAULT_ADDITIVE_INCREASE: f32 = 1.0;
+const DEFAULT_MULTIPLICATIVE_DECREASE: f32 = 0.5;
+
+/// Parameters of the AIMD send window kept for every destination
+#[derive(Debug, Clone, Copy)]
+pub struct CongestionConfig {
+    /// When disabled every fragment of a session is sent in one burst
+    pub enabled: bool,
+    /// Window of a destination that has not been contacted yet
+    pub initial_window: f32,
+    /// Lower bound of the window after a decrease
+    pub min_window: f32,
+    /// Upper bound of the window after an increase
+    pub max_window: f32,
+    /// Growth of the window over a full window of Acks
+    pub additive_increase: f32,
+    /// Factor applied to the window when a fragment is dropped
+    pub multiplicative_decrease: f32,
+}
+
+impl Default for CongestionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_window: DEFAULT_INITIAL_WINDOW,
+            min_window: DEFAULT_MIN_WINDOW,
+            max_window: DEFAULT_MAX_WINDOW,
+            additive_increase: DEFAULT_ADDITIVE_INCREASE,
+            multiplicative_decrease: DEFAULT_MULTIPLICATIVE_DECREASE,
+        }
+    }
+}
+
+/// Send window and queue of fragments waiting for it, for a single destination
+#[derive(Debug, Clone)]
+pub(crate) struct CongestionWindow {
+    /// Maximum number of fragments in flight (not yet acked)
+    pub(crate) size: f32,
+    /// Fragments waiting for room in the window, in sending order
+    pub(crate) queue: VecDeque<Packet>,
+    /// Number of fragments sent to the destination and not acked yet
+    pub(crate) in_flight: usize,
+    /// Number of times the window was shrunk by a drop
+    pub(crate) decreases: u64,
+}
+
+impl CongestionWindow {
+    fn new(size: f32) -> Self {
+        Self {
+            size,
+            queue: VecDeque::new(),
+            in_flight: 0,
+            decreases: 0,
+        }
+    }
+}
+
+impl RustbustersClient {
+    /// Queues the fragments of a new session and sends as many as the window
+    /// of the destination allows.
+    ///
+    /// ### Arguments
+    /// * `destination` - The destination of the session
+    /// * `packets` - The fragments of the session, in order
+    pub(crate) fn enqueue_fragments(&mut self, destination: NodeId, packets: Vec<Packet>) {
+        let initial_window = self.congestion_config.initial_window;
+        self.congestion_windows
+            .entry(destination)
+            .or_insert_with(|| CongestionWindow::new(initial_window))
+            .queue
+            .extend(packets);
+
+        self.release_queued_fragments(destination);
+    }
+
+    /// Sends queued fragments of a destination until its window is full.
+    ///
+    /// ### Arguments
+    /// * `destination` - The destination whose queue is drained
+    pub(crate) fn release_queued_fragments(&mut self, destination: NodeId) {
+        loop {
+            let Some(window) = self.congestion_windows.get_mut(&destination) else {
+                return;
+            };
+            if self.congestion_config.enabled && window.in_flight as f32 >= window.size.floor() {
+                if !window.queue.is_empty() {
+                    debug!(
+                        "Client {}: Window to {} full ({} in flight), {} fragments queued",
+                        self.id,
+                        destination,
+                        window.in_flight,
+                        window.queue.len()
+                    );
+                }
+                return;
+            }
+            let Some(packet) = window.queue.pop_front() else {
+                return;
+            };
+
+            self.transmit_fragment(packet);
+        }
+    }
+
+    /// Sends queued fragments of every destination whose window has room.
+    pub(crate) fn release_all_queued_fragments(&mut self) {
+        let destinations: Vec<NodeId> = self
+            .congestion_windows
+            .iter()
+            .filter(|(_, window)| !window.queue.is_empty())
+            .map(|(destination, _)| *destination)
+            .collect();
+
+        for destination in destinations {
+            self.release_queued_fragments(destination);
+        }
+    }
+
+    /// Grows the window of a destination after an Ack (additive increase).
+    ///
+    /// ### Arguments
+    /// * `destination` - The destination that acked a fragment
+    pub(crate) fn on_fragment_acked(&mut self, destination: NodeId) {
+        let config = self.congestion_config;
+        if let Some(window) = self.congestion_windows.get_mut(&destination) {
+            window.size =
+                (window.size + config.additive_increase / window.size).min(config.max_window);
+        }
+    }
+
+    /// Shrinks the window of a destination after a drop or an Ack timeout
+    /// (multiplicative decrease).
+    ///
+    /// ### Arguments
+    /// * `destination` - The destination of the dropped fragment
+    pub(crate) fn on_fragment_dropped(&mut self, destination: NodeId) {
+        let config = self.congestion_config;
+        if let Some(window) = self.congestion_windows.get_mut(&destination) {
+            window.size = (window.size * config.multiplicative_decrease).max(config.min_window);
+            window.decreases += 1;
+            info!(
+                "Client {}: Window to {} shrunk to {:.1} after a drop ({} decreases)",
+                self.id, destination, window.size, window.decreases
+            );
+        }
+    }
+
+    /// Drops the fragments of a session that are still waiting for the window.
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the session
+    pub(crate) fn discard_queued_fragments(&mut self, session_id: u64) {
+        for window in self.congestion_windows.values_mut() {
+            window
+                .queue
+                .retain(|packet| packet.session_id != session_id);
+        }
+    }
+
+    /// Whether some fragments of a session are still waiting for the window
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the session
+    pub(crate) fn has_queued_fragments(&self, session_id: u64) -> bool {
+        self.congestion_windows.values().any(|window| {
+            window
+                .queue
+                .iter()
+                .any(|packet| packet.session_id == session_id)
+        })
+    }
+
+    /// Counts a fragment sent to a destination as in flight until it is acked.
+    ///
+    /// ### Arguments
+    /// * `destination` - The destination of the fragment
+    pub(crate) fn fragment_in_flight(&mut self, destination: NodeId) {
+        let initial_window = self.congestion_config.initial_window;
+        self.congestion_windows
+            .entry(destination)
+            .or_insert_with(|| CongestionWindow::new(initial_window))
+            .in_flight += 1;
+    }
+
+    /// Stops counting fragments of a destination as in flight, after an Ack
+    /// or when their session is given up.
+    ///
+    /// ### Arguments
+    /// * `destination` - The destination of the fragments
+    /// * `count` - The number of fragments no longer in flight
+    pub(crate) fn fragments_landed(&mut self, destination: NodeId, count: usize) {
+        if let Some(window) = self.congestion_windows.get_mut(&destination) {
+            window.in_flight = window.in_flight.saturating_sub(count);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::client::RustbustersClient;
+use common_utils::{HostEvent, HostMessage};
+use serde::{Serialize, Serializer};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wg_2024::network::NodeId;
+
+const DEFAULT_JOURNAL_CAPACITY: usize = 1024;
+
+/// Size of the journal of the client events
+#[derive(Debug, Clone, Copy)]
+pub struct EventJournalConfig {
+    /// When disabled the events are only reported to the SC, when possible
+    pub enabled: bool,
+    /// Events kept in the journal, the oldest ones are dropped first
+    pub capacity: usize,
+}
+
+impl Default for EventJournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capacity: DEFAULT_JOURNAL_CAPACITY,
+        }
+    }
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/// Something that happened in the client, tagged by `kind`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClientEvent {
+    /// Every fragment of a session was acknowledged
+    MessageDelivered {
+        session_id: u64,
+        destination: NodeId,
+        message: HostMessage,
+        #[serde(rename = "latency_ms", serialize_with = "serialize_millis")]
+        latency: Duration,
+    },
+    /// A session was given up before all its fragments were acknowledged
+    SessionFailed {
+        session_id: u64,
+        destination: NodeId,
+        /// Route of the last fragments sent, empty if none was
+        route: Vec<NodeId>,
+        reason: String,
+    },
+    /// A fragment was sent again along a different route
+    FragmentRerouted {
+        session_id: u64,
+        fragment_index: u64,
+        destination: NodeId,
+        route: Vec<NodeId>,
+    },
+    /// A message was reassembled from its fragments
+    MessageReceived { session_id: u64, source: NodeId },
+    /// A reassembled message did not match its checksum
+    ReassemblyCorrupted {
+        session_id: u64,
+        source: NodeId,
+        error: String,
+    },
+    /// A partially received session got no fragment within the deadline
+    ReassemblyExpired {
+        session_id: u64,
+        received: u64,
+        total: usize,
+    },
+    /// A discovery flood of this client stopped bringing new edges
+    DiscoveryConverged {
+        flood_id: u64,
+        responses: u32,
+        duplicates: u32,
+    },
+    /// Nodes or edges were added to or removed from the topology
+    TopologyChanged {
+        reason: String,
+        nodes: usize,
+        edges: usize,
+    },
+}
+
+impl ClientEvent {
+    /// Returns the `HostEvent` reporting this event to the SC, if it has one.
+    ///
+    /// A failed session is reported as a Nack travelling its route back to
+    /// the client, the same header a drone dropping it would produce.
+    pub fn to_host_event(&self) -> Option<HostEvent> {
+        match self {
+            ClientEvent::MessageDelivered {
+                destination,
+                message,
+                latency,
+                ..
+            } => Some(HostEvent::HostMessageSent(
+                *destination,
+                message.clone(),
+                *latency,
+            )),
+            // No HostEvent reports a failure: the SC reads it from the journal
+            // and from the command results
+            ClientEvent::SessionFailed { .. } => None,
+            // The Acks, Nacks and resent fragments behind these already
+            // reach the SC as PacketSent
+            ClientEvent::FragmentRerouted { .. }
+            | ClientEvent::MessageReceived { .. }
+            | ClientEvent::ReassemblyCorrupted { .. } => None,
+            ClientEvent::ReassemblyExpired { .. }
+            | ClientEvent::DiscoveryConverged { .. }
+            | ClientEvent::TopologyChanged { .. } => None,
+        }
+    }
+}
+
+/// A recorded event
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    /// Position of the event among the ones of the client, from 0
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub event: ClientEvent,
+}
+
+/// The most recent events of a client
+#[derive(Debug)]
+pub(crate) struct EventJournal {
+    capacity: usize,
+    next_seq: u64,
+    entries: VecDeque<JournalEntry>,
+    // Whether network discovery has settled, kept even when the journal is disabled
+    pub(crate) discovery_converged: bool,
+}
+
+impl EventJournal {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_seq: 0,
+            entries: VecDeque::new(),
+            discovery_converged: false,
+        }
+    }
+
+    /// Records an event, dropping the oldest one when full
+    ///
+    /// ### Arguments
+    /// * `event` - The event
+    pub(crate) fn push(&mut self, event: ClientEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(JournalEntry {
+            seq: self.next_seq,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            event,
+        });
+        self.next_seq += 1;
+    }
+
+    /// Returns the events recorded after a given one, oldest first
+    ///
+    /// ### Arguments
+    /// * `after` - Only the events newer than this sequence number, if given
+    /// * `limit` - Maximum number of events, the oldest ones are kept
+    pub(crate) fn entries(&self, after: Option<u64>, limit: usize) -> Vec<JournalEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| after.is_none_or(|after| entry.seq > after))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Read access to the journal of a client, usable after the client moved
+/// to its own thread
+#[derive(Debug, Clone)]
+pub struct EventJournalReader {
+    journal: Arc<Mutex<EventJournal>>,
+}
+
+impl EventJournalReader {
+    /// Returns the events recorded after a given one, oldest first
+    ///
+    /// ### Arguments
+    /// * `after` - Only the events newer than this sequence number, if given,
+    ///   so that a replay can resume where the previous read stopped
+    /// * `limit` - Maximum number of events
+    pub fn entries(&self, after: Option<u64>, limit: usize) -> Vec<JournalEntry> {
+        self.journal.lock().unwrap().entries(after, limit)
+    }
+
+    /// Whether network discovery has settled: at least one flood converged
+    /// and none is still outstanding
+    pub fn is_discovery_converged(&self) -> bool {
+        self.journal.lock().unwrap().discovery_converged
+    }
+}
+
+impl RustbustersClient {
+    /// Returns a reader of the journal of the events of the client
+    pub fn event_journal(&self) -> EventJournalReader {
+        EventJournalReader {
+            journal: self.journal.clone(),
+        }
+    }
+
+    /// Records an event in the journal and reports it to the SC, when it
+    /// maps onto a `HostEvent`
+    ///
+    /// ### Arguments
+    /// * `event` - The event
+    pub(crate) fn record_event(&mut self, event: ClientEvent) {
+        if let Some(host_event) = event.to_host_event() {
+            self.send_to_sc(host_event);
+        }
+        if self.journal_config.enabled {
+            self.journal.lock().unwrap().push(event);
+        }
+    }
+
+    /// Drops the cached routes and records a change of the topology graph
+    ///
+    /// ### Arguments
+    /// * `reason` - What changed
+    pub(crate) fn topology_changed(&mut self, reason: &str) {
+        self.invalidate_route_cache(reason);
+        self.record_event(ClientEvent::TopologyChanged {
+            reason: reason.to_string(),
+            nodes: self.topology.node_count(),
+            edges: self.topology.edge_count(),
+        });
+    }
+}
@@ -1,60 +1,74 @@
+use crate::client::fragmentation::framing::decode_payload;
 use crate::client::RustbustersClient;
 use common_utils::HostMessage;
+use std::fmt::{Display, Formatter};
+
+/// Reasons why a session cannot be turned back into a message
+#[derive(Debug, PartialEq)]
+pub(crate) enum ReassemblyError {
+    /// There is no pending session with the given ID
+    UnknownSession(u64),
+    /// Some fragments of the session are missing
+    MissingFragment,
+    /// The payload checksum does not match: fragments were corrupted or mis-ordered
+    ChecksumMismatch { expected: u32, computed: u32 },
+    /// The payload is malformed or could not be deserialized
+    Malformed(String),
+}
+
+impl Display for ReassemblyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReassemblyError::UnknownSession(session_id) => {
+                write!(f, "No fragments for session {session_id}")
+            }
+            ReassemblyError::MissingFragment => write!(f, "Error in reassembly"),
+            ReassemblyError::ChecksumMismatch { expected, computed } => write!(
+                f,
+                "Checksum mismatch: expected {expected:08x}, computed {computed:08x}"
+            ),
+            ReassemblyError::Malformed(err) => write!(f, "{err}"),
+        }
+    }
+}
 
 impl RustbustersClient {
     /// Reassembles a complete message from its fragments.
     ///
     /// This function takes a session ID, retrieves all fragments associated with that session,
     /// and attempts to reconstruct the original message. It performs the following steps:
-    /// 1. Concatenates all fragment data
-    /// 2. Finds the effective string length (up to first zero)
-    /// 3. Converts byte array to string
-    /// 4. Deserializes the JSON string into a HostMessage
+    /// 1. Concatenates the first `length` bytes of every fragment
+    /// 2. Detects whether the payload is framed or legacy zero-terminated JSON
+    /// 3. Checks the payload length and checksum announced by the frame header
+    /// 4. Deserializes the payload into a HostMessage with the announced codec
     ///
     /// ### Arguments
     /// * `session_id` - The ID of the session to reassemble
     ///
     /// ### Returns
     /// * `Ok(HostMessage)` - If reassembly is successful
-    /// * `Err(String)` - If any step of the reassembly fails
-    pub(crate) fn reassemble_fragments(&mut self, session_id: u64) -> Result<HostMessage, String> {
+    /// * `Err(ReassemblyError)` - If any step of the reassembly fails
+    pub(crate) fn reassemble_fragments(
+        &mut self,
+        session_id: u64,
+    ) -> Result<HostMessage, ReassemblyError> {
+        self.reassembly_timestamps.remove(&session_id);
         match self.pending_received.remove(&session_id) {
-            None => Err(format!("No fragments for session {}", session_id)),
+            None => Err(ReassemblyError::UnknownSession(session_id)),
             Some(fragments) => {
-                let concatenated: Result<Vec<u8>, &str> =
-                    fragments
-                        .0
-                        .into_iter()
-                        .try_fold(Vec::new(), |mut acc, f| match f {
-                            Some(fragment) => {
-                                acc.extend_from_slice(&fragment.data);
-                                Ok(acc)
-                            }
-                            None => Err("Missing fragment"),
-                        });
-
-                if let Ok(byte_array) = concatenated {
-                    // Find the effective string length (up to first zero)
-                    let len = byte_array
-                        .iter()
-                        .position(|&x| x == 0)
-                        .unwrap_or(byte_array.len());
-
-                    // Convert byte array to string
-                    let serialized_str = std::str::from_utf8(&byte_array[..len]);
-                    let serialized_str = match serialized_str {
-                        Ok(s) => s,
-                        Err(_) => return Err("Error in JSON string conversion".to_string()),
-                    };
+                let concatenated: Result<Vec<u8>, ReassemblyError> = fragments
+                    .0
+                    .into_iter()
+                    .try_fold(Vec::new(), |mut acc, f| match f {
+                        Some(fragment) => {
+                            let length = (fragment.length as usize).min(fragment.data.len());
+                            acc.extend_from_slice(&fragment.data[..length]);
+                            Ok(acc)
+                        }
+                        None => Err(ReassemblyError::MissingFragment),
+                    });
 
-                    if let Ok(msg) = serde_json::from_str(serialized_str) {
-                        Ok(msg)
-                    } else {
-                        Err("Error in deserialization".to_string())
-                    }
-                } else {
-                    Err("Error in reassembly".to_string())
-                }
+                decode_payload(&concatenated?).map(|(msg, _)| msg)
             }
         }
     }
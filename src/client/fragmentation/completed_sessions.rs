@@ -0,0 +1,48 @@
+use std::collections::{HashSet, VecDeque};
+use wg_2024::network::NodeId;
+
+/// Default number of completed sessions remembered for duplicate detection
+pub(crate) const DEFAULT_COMPLETED_SESSIONS_CAPACITY: usize = 256;
+
+/// Bounded record of the most recently reassembled sessions.
+///
+/// Late duplicates of their fragments must be acknowledged again
+/// but never delivered to the UI a second time. Also records the sessions
+/// that failed verification, whose fragments must not be acknowledged.
+#[derive(Debug)]
+pub(crate) struct CompletedSessions {
+    capacity: usize,
+    order: VecDeque<(NodeId, u64)>,
+    sessions: HashSet<(NodeId, u64)>,
+}
+
+impl CompletedSessions {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            sessions: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Remembers a session, forgetting the oldest one when full
+    ///
+    /// ### Arguments
+    /// * `source` - The node that sent the session
+    /// * `session_id` - The ID of the completed session
+    pub(crate) fn insert(&mut self, source: NodeId, session_id: u64) {
+        if self.capacity == 0 || !self.sessions.insert((source, session_id)) {
+            return;
+        }
+        self.order.push_back((source, session_id));
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.sessions.remove(&oldest);
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, source: NodeId, session_id: u64) -> bool {
+        self.sessions.contains(&(source, session_id))
+    }
+}
@@ -0,0 +1,70 @@
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+use wg_2024::packet::FRAGMENT_DSIZE;
+
+/// Default minimum size of a message body before compression is attempted
+pub(crate) const DEFAULT_COMPRESSION_THRESHOLD: usize = 2 * FRAGMENT_DSIZE;
+/// Upper bound of a decompressed body, to reject malicious payloads
+const MAX_DECOMPRESSED_LEN: u64 = 64 * 1024 * 1024;
+
+/// Statistics about the compression of outgoing messages
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct CompressionStats {
+    /// Number of messages sent compressed
+    pub(crate) messages_compressed: u64,
+    /// Size of the compressed messages before compression
+    pub(crate) bytes_before: u64,
+    /// Size of the compressed messages after compression
+    pub(crate) bytes_after: u64,
+    /// Fragments not sent thanks to compression
+    pub(crate) fragments_saved: u64,
+}
+
+impl CompressionStats {
+    /// Records the compression of a message
+    ///
+    /// ### Arguments
+    /// * `bytes_before` - Size of the payload without compression
+    /// * `bytes_after` - Size of the compressed payload
+    pub(crate) fn record(&mut self, bytes_before: usize, bytes_after: usize) {
+        self.messages_compressed += 1;
+        self.bytes_before += bytes_before as u64;
+        self.bytes_after += bytes_after as u64;
+        self.fragments_saved += fragments_saved(bytes_before, bytes_after);
+    }
+
+    /// Overall compressed/uncompressed size ratio, 1.0 if nothing was compressed
+    pub(crate) fn ratio(&self) -> f32 {
+        if self.bytes_before == 0 {
+            1.0
+        } else {
+            self.bytes_after as f32 / self.bytes_before as f32
+        }
+    }
+}
+
+/// Number of fragments spared by sending `bytes_after` bytes instead of `bytes_before`
+pub(crate) fn fragments_saved(bytes_before: usize, bytes_after: usize) -> u64 {
+    bytes_before
+        .div_ceil(FRAGMENT_DSIZE)
+        .saturating_sub(bytes_after.div_ceil(FRAGMENT_DSIZE)) as u64
+}
+
+/// Compresses a message body with deflate
+pub(crate) fn compress(body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+/// Decompresses a deflate message body
+pub(crate) fn decompress(body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decompressed = Vec::new();
+    DeflateDecoder::new(body)
+        .take(MAX_DECOMPRESSED_LEN)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| format!("Error in decompression: {e}"))?;
+    Ok(decompressed)
+}
@@ -1,46 +1,106 @@
+use crate::client::fragmentation::compression::fragments_saved;
+use crate::client::fragmentation::framing::{encode_payload, PayloadEncoding};
 use crate::client::RustbustersClient;
 use common_utils::HostMessage;
+use log::info;
+use wg_2024::network::NodeId;
 use wg_2024::packet::{Fragment, FRAGMENT_DSIZE};
 
 impl RustbustersClient {
     /// Splits a message into multiple fragments of fixed size.
     ///
-    /// This function serializes a message and splits it into fragments that can be
-    /// transmitted over the network. Each fragment contains:
-    /// - fragment_index: Position in the sequence
-    /// - total_n_fragments: Total number of fragments
-    /// - length: Actual data length in the fragment
-    /// - data: Fixed-size array containing the fragment data
+    /// The message is serialized with the given encoding and, if enabled and
+    /// worthwhile, compressed before being split.
     ///
     /// ### Arguments
     /// * `message` - The message to be fragmented
+    /// * `encoding` - The payload encoding to use
     ///
     /// ### Returns
-    /// A vector containing all fragments of the message
-    pub(crate) fn disassemble_message(&self, message: &HostMessage) -> Vec<Fragment> {
-        let serialized_str = serde_json::to_string(&message).unwrap();
-        let bytes = serialized_str.as_bytes();
-
-        // Fragment the data into chunks of FRAGMENT_DSIZE bytes
-        let total_size = bytes.len();
-        let total_n_fragments = total_size.div_ceil(FRAGMENT_DSIZE) as u64;
-
-        let mut fragments = Vec::new();
-        for (i, chunk) in bytes.chunks(FRAGMENT_DSIZE).enumerate() {
-            let mut data_array = [0u8; FRAGMENT_DSIZE];
-            let length = chunk.len();
-            data_array[..length].copy_from_slice(chunk);
-
-            let fragment = Fragment {
-                fragment_index: i as u64,
-                total_n_fragments,
-                length: length as u8,
-                data: data_array,
-            };
-
-            fragments.push(fragment);
+    /// A vector containing all fragments of the message, or the reason it
+    /// could not be encoded
+    pub(crate) fn disassemble_message(
+        &mut self,
+        message: &HostMessage,
+        encoding: PayloadEncoding,
+    ) -> Result<Vec<Fragment>, String> {
+        let compression_threshold = self
+            .framing_config
+            .compression
+            .then_some(self.framing_config.compression_threshold);
+        let payload = encode_payload(encoding, message, compression_threshold)?;
+
+        if payload.is_compressed() {
+            self.compression_stats
+                .record(payload.uncompressed_len, payload.bytes.len());
+            info!(
+                "Client {}: Compressed payload from {} to {} bytes, {} fragments saved (overall ratio {:.2})",
+                self.id,
+                payload.uncompressed_len,
+                payload.bytes.len(),
+                fragments_saved(payload.uncompressed_len, payload.bytes.len()),
+                self.compression_stats.ratio()
+            );
+        }
+
+        Ok(split_payload(&payload.bytes))
+    }
+
+    /// Payload encoding for peers whose capabilities are unknown
+    pub(crate) fn default_payload_encoding(&self) -> PayloadEncoding {
+        if self.framing_config.framed_by_default || self.framing_config.compression {
+            PayloadEncoding::Framed(self.framing_config.codec)
+        } else {
+            PayloadEncoding::Legacy
         }
+    }
 
-        fragments
+    /// Chooses the payload encoding for a destination: the codec it used in its
+    /// last framed message if any, otherwise the default one.
+    ///
+    /// ### Arguments
+    /// * `destination` - The ID of the destination node
+    pub(crate) fn payload_encoding_for(&self, destination: NodeId) -> PayloadEncoding {
+        self.peer_encodings
+            .get(&destination)
+            .copied()
+            .unwrap_or_else(|| self.default_payload_encoding())
     }
 }
+
+/// Splits a payload into fragments of fixed size.
+///
+/// Each fragment contains:
+/// - fragment_index: Position in the sequence
+/// - total_n_fragments: Total number of fragments
+/// - length: Actual data length in the fragment
+/// - data: Fixed-size array containing the fragment data
+///
+/// ### Arguments
+/// * `bytes` - The serialized payload
+///
+/// ### Returns
+/// A vector containing all fragments of the payload
+fn split_payload(bytes: &[u8]) -> Vec<Fragment> {
+    // Fragment the data into chunks of FRAGMENT_DSIZE bytes
+    let total_size = bytes.len();
+    let total_n_fragments = total_size.div_ceil(FRAGMENT_DSIZE) as u64;
+
+    let mut fragments = Vec::new();
+    for (i, chunk) in bytes.chunks(FRAGMENT_DSIZE).enumerate() {
+        let mut data_array = [0u8; FRAGMENT_DSIZE];
+        let length = chunk.len();
+        data_array[..length].copy_from_slice(chunk);
+
+        let fragment = Fragment {
+            fragment_index: i as u64,
+            total_n_fragments,
+            length: length as u8,
+            data: data_array,
+        };
+
+        fragments.push(fragment);
+    }
+
+    fragments
+}
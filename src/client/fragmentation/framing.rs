@@ -0,0 +1,281 @@
+use crate::client::fragmentation::assembler::ReassemblyError;
+use crate::client::fragmentation::compression::{
+    compress, decompress, DEFAULT_COMPRESSION_THRESHOLD,
+};
+use common_utils::HostMessage;
+use wg_2024::packet::Fragment;
+
+/// First byte of a framed payload. It can never start a legacy JSON payload.
+pub(crate) const FRAME_MAGIC: u8 = 0xB5;
+/// Version of the frame header layout
+pub(crate) const FRAME_VERSION: u8 = 1;
+/// Size of the frame header at the start of the first fragment:
+/// magic (1) | version (1) | codec (1) | flags (1) | body length (4, big endian)
+pub(crate) const FRAME_HEADER_LEN: usize = 8;
+/// Flag set when the body is deflate-compressed
+pub(crate) const FLAG_COMPRESSED: u8 = 0b0000_0001;
+/// Flag set when a CRC32 of the body follows the header
+pub(crate) const FLAG_CHECKSUM: u8 = 0b0000_0010;
+/// Size of the CRC32 (big endian) placed between the header and the body
+const CHECKSUM_LEN: usize = 4;
+
+/// Serialization format of the message carried in a framed payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadCodec {
+    /// JSON, the format understood by every node of the network
+    Json,
+    /// CBOR, a compact binary format
+    Cbor,
+}
+
+impl PayloadCodec {
+    pub(crate) fn tag(self) -> u8 {
+        match self {
+            PayloadCodec::Json => 0,
+            PayloadCodec::Cbor => 1,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(PayloadCodec::Json),
+            1 => Some(PayloadCodec::Cbor),
+            _ => None,
+        }
+    }
+
+    fn encode(self, message: &HostMessage) -> Result<Vec<u8>, String> {
+        match self {
+            PayloadCodec::Json => serde_json::to_vec(message).map_err(|e| e.to_string()),
+            PayloadCodec::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(message, &mut bytes).map_err(|e| e.to_string())?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> Result<HostMessage, String> {
+        match self {
+            PayloadCodec::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            PayloadCodec::Cbor => ciborium::from_reader(bytes).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Encoding of the payload of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PayloadEncoding {
+    /// Zero-terminated JSON without header, for nodes that do not support framing
+    Legacy,
+    /// Frame header followed by the message encoded with the given codec
+    Framed(PayloadCodec),
+}
+
+/// Parameters of the payload encoding of outgoing messages
+#[derive(Debug, Clone, Copy)]
+pub struct FramingConfig {
+    /// Codec used for framed payloads
+    pub codec: PayloadCodec,
+    /// Whether to frame messages to peers that never sent a framed payload.
+    /// When neither this nor `compression` is set, those peers get the legacy
+    /// zero-terminated JSON understood by every node.
+    pub framed_by_default: bool,
+    /// Whether to compress payloads when it makes them smaller. Only framed
+    /// payloads can be compressed, so this also frames messages to peers that
+    /// never sent a payload.
+    pub compression: bool,
+    /// Minimum body size, in bytes, before compression is attempted
+    pub compression_threshold: usize,
+}
+
+impl Default for FramingConfig {
+    fn default() -> Self {
+        Self {
+            codec: PayloadCodec::Json,
+            framed_by_default: false,
+            compression: false,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+        }
+    }
+}
+
+/// Payload of a session ready to be fragmented
+#[derive(Debug)]
+pub(crate) struct EncodedPayload {
+    pub(crate) bytes: Vec<u8>,
+    /// Size the payload would have without compression
+    pub(crate) uncompressed_len: usize,
+}
+
+impl EncodedPayload {
+    pub(crate) fn is_compressed(&self) -> bool {
+        self.bytes.len() < self.uncompressed_len
+    }
+}
+
+/// Serializes a message into the payload of a session
+///
+/// ### Arguments
+/// * `encoding` - The payload encoding to use
+/// * `message` - The message to be serialized
+/// * `compression_threshold` - Minimum body size to attempt compression, `None` to disable it.
+///   Only framed payloads can be compressed.
+pub(crate) fn encode_payload(
+    encoding: PayloadEncoding,
+    message: &HostMessage,
+    compression_threshold: Option<usize>,
+) -> Result<EncodedPayload, String> {
+    match encoding {
+        PayloadEncoding::Legacy => {
+            let bytes = PayloadCodec::Json.encode(message)?;
+            Ok(EncodedPayload {
+                uncompressed_len: bytes.len(),
+                bytes,
+            })
+        }
+        PayloadEncoding::Framed(codec) => {
+            let mut body = codec.encode(message)?;
+            let uncompressed_len = FRAME_HEADER_LEN + CHECKSUM_LEN + body.len();
+            let mut flags = FLAG_CHECKSUM;
+
+            // Keep the compressed body only if it actually shrinks the payload
+            if compression_threshold.is_some_and(|threshold| body.len() >= threshold) {
+                let compressed = compress(&body)?;
+                if compressed.len() < body.len() {
+                    body = compressed;
+                    flags |= FLAG_COMPRESSED;
+                }
+            }
+
+            let body_len = u32::try_from(body.len())
+                .map_err(|_| format!("Payload too large: {} bytes", body.len()))?;
+
+            let mut payload = Vec::with_capacity(FRAME_HEADER_LEN + CHECKSUM_LEN + body.len());
+            payload.push(FRAME_MAGIC);
+            payload.push(FRAME_VERSION);
+            payload.push(codec.tag());
+            payload.push(flags);
+            payload.extend_from_slice(&body_len.to_be_bytes());
+            // The checksum covers the body as transmitted, after compression
+            payload.extend_from_slice(&crc32fast::hash(&body).to_be_bytes());
+            payload.extend_from_slice(&body);
+            Ok(EncodedPayload {
+                bytes: payload,
+                uncompressed_len,
+            })
+        }
+    }
+}
+
+/// Deserializes the payload of a session, detecting whether it is framed
+///
+/// ### Arguments
+/// * `payload` - The concatenated data of all the fragments
+///
+/// ### Returns
+/// The decoded message together with the encoding used by the sender
+pub(crate) fn decode_payload(
+    payload: &[u8],
+) -> Result<(HostMessage, PayloadEncoding), ReassemblyError> {
+    if payload.first() != Some(&FRAME_MAGIC) {
+        return decode_legacy_payload(payload)
+            .map(|msg| (msg, PayloadEncoding::Legacy))
+            .map_err(ReassemblyError::Malformed);
+    }
+
+    if payload.len() < FRAME_HEADER_LEN {
+        return Err(ReassemblyError::Malformed(format!(
+            "Truncated frame header: {} bytes",
+            payload.len()
+        )));
+    }
+    if payload[1] != FRAME_VERSION {
+        return Err(ReassemblyError::Malformed(format!(
+            "Unsupported frame version {}",
+            payload[1]
+        )));
+    }
+    let codec = PayloadCodec::from_tag(payload[2]).ok_or_else(|| {
+        ReassemblyError::Malformed(format!("Unknown payload codec {}", payload[2]))
+    })?;
+    let flags = payload[3];
+    let body_len = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]) as usize;
+
+    let mut body = &payload[FRAME_HEADER_LEN..];
+    let mut expected_checksum = None;
+    if flags & FLAG_CHECKSUM != 0 {
+        if body.len() < CHECKSUM_LEN {
+            return Err(ReassemblyError::Malformed(
+                "Truncated frame checksum".to_string(),
+            ));
+        }
+        expected_checksum = Some(u32::from_be_bytes([body[0], body[1], body[2], body[3]]));
+        body = &body[CHECKSUM_LEN..];
+    }
+
+    if body.len() != body_len {
+        return Err(ReassemblyError::Malformed(format!(
+            "Payload length mismatch: expected {} bytes, got {}",
+            body_len,
+            body.len()
+        )));
+    }
+
+    // A mismatch means corrupted or mis-ordered fragments, not a codec bug
+    if let Some(expected) = expected_checksum {
+        let computed = crc32fast::hash(body);
+        if computed != expected {
+            return Err(ReassemblyError::ChecksumMismatch { expected, computed });
+        }
+    }
+
+    let decompressed;
+    let body = if flags & FLAG_COMPRESSED != 0 {
+        decompressed = decompress(body).map_err(ReassemblyError::Malformed)?;
+        &decompressed[..]
+    } else {
+        body
+    };
+
+    codec
+        .decode(body)
+        .map(|msg| (msg, PayloadEncoding::Framed(codec)))
+        .map_err(|e| ReassemblyError::Malformed(format!("Error in deserialization: {e}")))
+}
+
+/// Decodes a zero-terminated JSON payload
+fn decode_legacy_payload(payload: &[u8]) -> Result<HostMessage, String> {
+    // Find the effective string length (up to first zero)
+    let len = payload
+        .iter()
+        .position(|&x| x == 0)
+        .unwrap_or(payload.len());
+
+    // Convert byte array to string
+    let serialized_str = match std::str::from_utf8(&payload[..len]) {
+        Ok(s) => s,
+        Err(_) => return Err("Error in JSON string conversion".to_string()),
+    };
+
+    serde_json::from_str(serialized_str).map_err(|_| "Error in deserialization".to_string())
+}
+
+/// Reads the payload encoding announced by the first fragment of a session
+///
+/// ### Arguments
+/// * `first_fragment` - The fragment with index 0
+pub(crate) fn fragment_encoding(first_fragment: &Fragment) -> Option<PayloadEncoding> {
+    let data = first_fragment.data.get(..first_fragment.length as usize)?;
+    if data.first() != Some(&FRAME_MAGIC) {
+        return Some(PayloadEncoding::Legacy);
+    }
+    // The codec tag of another version of the header may mean something else
+    if data.get(1) != Some(&FRAME_VERSION) {
+        return None;
+    }
+    data.get(2)
+        .copied()
+        .and_then(PayloadCodec::from_tag)
+        .map(PayloadEncoding::Framed)
+}
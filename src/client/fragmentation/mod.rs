@@ -1,2 +1,14 @@
 mod assembler;
+mod completed_sessions;
+mod compression;
 mod disassembler;
+mod framing;
+mod reassembly_timeout;
+
+pub(crate) use assembler::ReassemblyError;
+pub(crate) use completed_sessions::{CompletedSessions, DEFAULT_COMPLETED_SESSIONS_CAPACITY};
+pub(crate) use compression::CompressionStats;
+pub(crate) use framing::{fragment_encoding, PayloadEncoding};
+pub use framing::{FramingConfig, PayloadCodec};
+pub use reassembly_timeout::ReassemblyConfig;
+pub(crate) use reassembly_timeout::ReassemblyTimestamps;
@@ -0,0 +1,127 @@
+use crate::client::events::ClientEvent;
+use crate::client::RustbustersClient;
+use log::{info, warn};
+use std::time::{Duration, Instant};
+
+const DEFAULT_REASSEMBLY_DEADLINE: Duration = Duration::from_secs(30);
+const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_FRAGMENTS: u64 = 1 << 16;
+
+/// Parameters of the garbage collection of incomplete received sessions
+#[derive(Debug, Clone, Copy)]
+pub struct ReassemblyConfig {
+    /// Maximum time without new fragments before a partial session is dropped
+    pub deadline: Duration,
+    /// How often `pending_received` is swept for expired sessions
+    pub sweep_interval: Duration,
+    /// Whether a session whose checksum does not match is asked again with a
+    /// single Nack. Otherwise it is left unacked until the sender gives it up.
+    pub retransmit_corrupted: bool,
+    /// Sessions announcing more fragments than this are rejected before
+    /// any memory is reserved for them
+    pub max_fragments: u64,
+}
+
+impl Default for ReassemblyConfig {
+    fn default() -> Self {
+        Self {
+            deadline: DEFAULT_REASSEMBLY_DEADLINE,
+            sweep_interval: DEFAULT_SWEEP_INTERVAL,
+            retransmit_corrupted: false,
+            max_fragments: DEFAULT_MAX_FRAGMENTS,
+        }
+    }
+}
+
+/// Reception timestamps of a partially received session
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReassemblyTimestamps {
+    first_seen: Instant,
+    last_seen: Instant,
+}
+
+impl RustbustersClient {
+    /// Records the arrival of a fragment for a session being reassembled.
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the message session
+    pub(crate) fn touch_reassembly_session(&mut self, session_id: u64) {
+        let now = Instant::now();
+        self.reassembly_timestamps
+            .entry(session_id)
+            .and_modify(|timestamps| timestamps.last_seen = now)
+            .or_insert(ReassemblyTimestamps {
+                first_seen: now,
+                last_seen: now,
+            });
+    }
+
+    pub(crate) fn should_sweep_reassemblies(&self) -> bool {
+        self.last_reassembly_sweep.elapsed() >= self.reassembly_config.sweep_interval
+    }
+
+    /// Drops every partially received session that got no fragment within the
+    /// reassembly deadline.
+    ///
+    /// ### Returns
+    /// The number of sessions dropped by this sweep
+    pub(crate) fn sweep_expired_reassemblies(&mut self) -> usize {
+        self.last_reassembly_sweep = Instant::now();
+        let deadline = self.reassembly_config.deadline;
+
+        // Sessions without timestamps (e.g. inserted directly) start aging now
+        for session_id in self.pending_received.keys() {
+            if !self.reassembly_timestamps.contains_key(session_id) {
+                let now = Instant::now();
+                self.reassembly_timestamps.insert(
+                    *session_id,
+                    ReassemblyTimestamps {
+                        first_seen: now,
+                        last_seen: now,
+                    },
+                );
+            }
+        }
+        self.reassembly_timestamps
+            .retain(|session_id, _| self.pending_received.contains_key(session_id));
+
+        let expired: Vec<(u64, ReassemblyTimestamps)> = self
+            .reassembly_timestamps
+            .iter()
+            .filter(|(_, timestamps)| timestamps.last_seen.elapsed() >= deadline)
+            .map(|(session_id, timestamps)| (*session_id, *timestamps))
+            .collect();
+
+        for (session_id, timestamps) in &expired {
+            self.reassembly_timestamps.remove(session_id);
+            if let Some((fragments, received)) = self.pending_received.remove(session_id) {
+                warn!(
+                    "Client {}: Dropping incomplete session {} ({}/{} fragments, first seen {:?} ago, last seen {:?} ago)",
+                    self.id,
+                    session_id,
+                    received,
+                    fragments.len(),
+                    timestamps.first_seen.elapsed(),
+                    timestamps.last_seen.elapsed(),
+                );
+                self.record_event(ClientEvent::ReassemblyExpired {
+                    session_id: *session_id,
+                    received,
+                    total: fragments.len(),
+                });
+            }
+        }
+
+        if !expired.is_empty() {
+            self.expired_reassemblies += expired.len() as u64;
+            info!(
+                "Client {}: Reassembly sweep dropped {} sessions ({} in total)",
+                self.id,
+                expired.len(),
+                self.expired_reassemblies
+            );
+        }
+
+        expired.len()
+    }
+}
@@ -1,5 +1,6 @@
+use crate::client::events::ClientEvent;
+use crate::client::outbox::MessageStatus;
 use crate::client::RustbustersClient;
-use common_utils::HostEvent;
 use log::{info, warn};
 
 impl RustbustersClient {
@@ -17,8 +18,16 @@ impl RustbustersClient {
     pub(crate) fn handle_ack(&mut self, session_id: u64, fragment_index: u64) {
         // Remove the acked fragment from the pending_sent list
         let acked = self.pending_sent.remove(&(session_id, fragment_index));
+        self.retransmission_timers
+            .remove(&(session_id, fragment_index));
         if let Some(packet) = acked {
             self.register_successful_transmission(&packet.routing_header.hops);
+            if let Some(destination) = packet.routing_header.hops.last() {
+                self.fragments_landed(*destination, 1);
+                self.on_fragment_acked(*destination);
+            }
+            self.acked_fragments
+                .insert((session_id, fragment_index), packet);
         } else {
             warn!(
                 "Client {}: Ack for unknown fragment with index {} and session_id {}",
@@ -26,24 +35,35 @@ impl RustbustersClient {
             );
         }
 
-        // Check if all fragments with key (session_id, _) have been acked
+        // Check if all fragments with key (session_id, _) have been acked,
+        // including the ones still waiting for the congestion window
         if self
             .pending_sent
             .iter()
             .filter(|((key, _), _)| *key == session_id)
             .collect::<Vec<_>>()
             .is_empty()
+            && !self.has_queued_fragments(session_id)
         {
-            let session_info = self
-                .pending_session_info
-                .get(&session_id)
-                .expect("Session not found");
-            let destination = session_info.0;
-            let message = session_info.1.clone();
-            let latency = session_info.2.elapsed();
+            // The session may already be completed (late duplicate Ack) or failed
+            let Some((destination, message, sent_at)) =
+                self.pending_session_info.remove(&session_id)
+            else {
+                return;
+            };
+            let latency = sent_at.elapsed();
+            self.metrics
+                .session_delivered(session_id, destination, latency);
+            self.complete_session_message(session_id, destination, MessageStatus::Delivered);
+            self.acked_fragments
+                .retain(|(key, _), _| *key != session_id);
 
-            self.send_to_sc(HostEvent::HostMessageSent(destination, message, latency));
-            self.pending_session_info.remove(&session_id);
+            self.record_event(ClientEvent::MessageDelivered {
+                session_id,
+                destination,
+                message,
+                latency,
+            });
 
             info!(
                 "Client {}: All fragments of session {} acked",
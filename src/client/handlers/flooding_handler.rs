@@ -1,9 +1,9 @@
+use crate::client::routing::edge_key;
 use crate::client::routing::edge_stats::BASE_WEIGHT;
 use crate::client::RustbustersClient;
 use common_utils::HostEvent::{ControllerShortcut, PacketSent};
 use common_utils::{PacketHeader, PacketTypeHeader};
-use log::info;
-use log::warn;
+use log::{debug, info, warn};
 use wg_2024::network::SourceRoutingHeader;
 use wg_2024::packet::NodeType::Client;
 use wg_2024::packet::{FloodRequest, FloodResponse, Packet, PacketType};
@@ -19,20 +19,46 @@ impl RustbustersClient {
     /// ### Arguments
     /// * `flood_response` - Contains the path trace of the flood through the network
     pub(crate) fn handle_flood_response(&mut self, flood_response: &FloodResponse) {
-        for window in flood_response.path_trace.windows(2) {
-            if let [(from_id, from_type), (to_id, to_type)] = window {
+        let path_trace = &flood_response.path_trace;
+        let edges = path_trace
+            .windows(2)
+            .map(|window| edge_key(window[0].0, window[1].0))
+            .collect();
+
+        // Responses of the same flood overlap: only the edges not yet seen in
+        // this round need to be processed
+        let new_edges = self.record_flood_response(flood_response.flood_id, edges);
+        if new_edges.is_empty() && path_trace.len() > 1 {
+            debug!(
+                "Client {}: Duplicate FloodResponse for flood_id {}",
+                self.id, flood_response.flood_id
+            );
+            return;
+        }
+
+        let mut changed = false;
+        {
+            let mut known_nodes = self.known_nodes.lock().unwrap();
+            for (node_id, node_type) in path_trace {
                 // Update known nodes
-                self.known_nodes
-                    .lock()
-                    .unwrap()
-                    .insert(*from_id, *from_type);
-                self.known_nodes.lock().unwrap().insert(*to_id, *to_type);
+                changed |= known_nodes.insert(*node_id, *node_type) != Some(*node_type);
+            }
+        }
 
-                // Update topology
-                self.topology.add_edge(*from_id, *to_id, BASE_WEIGHT);
+        for (from_id, to_id) in new_edges {
+            // Update topology, keeping the weight learned for known edges
+            if !self.topology.contains_edge(from_id, to_id) {
+                self.topology.add_edge(from_id, to_id, BASE_WEIGHT);
+                changed = true;
             }
         }
 
+        self.refresh_topology_ages(path_trace);
+
+        if changed {
+            self.topology_changed("topology changed by flood response");
+        }
+
         info!("Client {}: Updated topology: {:?}", self.id, self.topology);
         info!(
             "Client {}: Known nodes: {:?}",
@@ -49,6 +75,8 @@ impl RustbustersClient {
     /// 3. If not the initiator, routes the response back to the initiator
     ///    else uses the response to learn the network topology
     ///
+    /// Further copies of a flood already answered are ignored.
+    ///
     /// ### Arguments
     /// * `flood_request` - The received flood request
     /// * `session_id` - The ID of the flooding session
@@ -71,6 +99,16 @@ impl RustbustersClient {
             return;
         }
 
+        // Only the first copy is answered
+        let copies = self.record_flood_request(flood_request.initiator_id, flood_request.flood_id);
+        if copies > 1 {
+            debug!(
+                "Client {}: FloodRequest {} of {} received {} times, ignoring it",
+                self.id, flood_request.flood_id, flood_request.initiator_id, copies
+            );
+            return;
+        }
+
         // Create the packet
         let response_packet = Packet {
             pack_type: PacketType::FloodResponse(flood_response),
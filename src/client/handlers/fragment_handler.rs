@@ -1,11 +1,30 @@
+use crate::client::events::ClientEvent;
+use crate::client::fragmentation::{fragment_encoding, ReassemblyError};
 use crate::client::RustbustersClient;
 use common_utils::HostEvent::{ControllerShortcut, PacketSent};
 use common_utils::HostMessage::FromServer;
 use common_utils::{PacketHeader, PacketTypeHeader, ServerToClientMessage};
 use crossbeam_channel::Sender;
-use log::{info, warn};
+use log::{error, info, warn};
 use wg_2024::network::{NodeId, SourceRoutingHeader};
-use wg_2024::packet::{Ack, Fragment, Packet, PacketType};
+use wg_2024::packet::{Ack, Fragment, Nack, NackType, Packet, PacketType, FRAGMENT_DSIZE};
+
+/// Outcome of storing a received fragment
+#[derive(Debug, PartialEq)]
+pub(crate) enum FragmentReception {
+    /// The fragment was stored but other fragments are still missing
+    Incomplete,
+    /// The fragment was the last missing one of the session
+    Complete,
+    /// The fragment had already been received for this session
+    Duplicate,
+    /// The session has already been reassembled and delivered
+    AlreadyDelivered,
+    /// The session failed verification and was given up
+    Corrupted,
+    /// The fragment is inconsistent and cannot be stored
+    Invalid(String),
+}
 
 impl RustbustersClient {
     /// Handles incoming message fragments and sends acknowledgments.
@@ -16,6 +35,10 @@ impl RustbustersClient {
     /// 3. Sending acknowledgment back to the source
     /// 4. Forwarding reassembled messages to the UI and controller if complete
     ///
+    /// Duplicate fragments are acknowledged again but never delivered twice,
+    /// while inconsistent fragments are rejected with a Nack. No fragment of
+    /// a session that fails verification is acknowledged.
+    ///
     /// ### Arguments
     /// * `fragment` - The received message fragment
     /// * `session_id` - The ID of the message session
@@ -29,40 +52,145 @@ impl RustbustersClient {
         sender: &Sender<(NodeId, ServerToClientMessage)>,
     ) {
         let source = *source_routing_header.hops.first().unwrap();
+        let fragment_index = fragment.fragment_index;
 
-        // If after insert all fragments of the session are received, reassemble the message
-        if self.set_pending(session_id, fragment.clone()) {
-            match self.reassemble_fragments(session_id) {
-                Ok(msg) => {
-                    info!(
-                        "Client {}: Received full message {:?} of session {}",
-                        self.id, msg, session_id
-                    );
-
-                    if let FromServer(s2c_msg) = &msg {
-                        if sender.send((source, s2c_msg.clone())).is_err() {
-                            warn!("Client {}: Unable to send message to UI", self.id);
+        match self.set_pending(source, session_id, fragment.clone()) {
+            // If after insert all fragments of the session are received, reassemble the message
+            FragmentReception::Complete => {
+                let encoding = self
+                    .pending_received
+                    .get(&session_id)
+                    .and_then(|(fragments, _)| fragments.first()?.as_ref())
+                    .and_then(fragment_encoding);
+
+                match self.reassemble_fragments(session_id) {
+                    Ok(msg) => {
+                        info!(
+                            "Client {}: Received full message {:?} of session {}",
+                            self.id, msg, session_id
+                        );
+                        self.completed_sessions.insert(source, session_id);
+                        self.record_event(ClientEvent::MessageReceived { session_id, source });
+
+                        // Answer the peer with the same payload encoding it uses
+                        if let Some(encoding) = encoding {
+                            self.peer_encodings.insert(source, encoding);
                         }
-                    } else {
-                        warn!(
-                            "Client {}: Received message that is from another client",
-                            self.id
+
+                        if let FromServer(s2c_msg) = &msg {
+                            self.record_received_message(source, s2c_msg);
+                            if sender.send((source, s2c_msg.clone())).is_err() {
+                                warn!("Client {}: Unable to send message to UI", self.id);
+                            }
+                        } else {
+                            warn!(
+                                "Client {}: Received message that is from another client",
+                                self.id
+                            );
+                        }
+                    }
+                    Err(err @ ReassemblyError::ChecksumMismatch { .. }) => {
+                        self.integrity_failures += 1;
+                        error!(
+                            "Client {}: Session {} from {} is corrupted: {}",
+                            self.id, session_id, source, err
                         );
+                        self.record_event(ClientEvent::ReassemblyCorrupted {
+                            session_id,
+                            source,
+                            error: err.to_string(),
+                        });
+
+                        if self.reassembly_config.retransmit_corrupted {
+                            // A Dropped Nack from the receiver itself makes the
+                            // sender resend the whole session
+                            self.send_response_packet(
+                                PacketType::Nack(Nack {
+                                    fragment_index,
+                                    nack_type: NackType::Dropped,
+                                }),
+                                session_id,
+                                source_routing_header,
+                            );
+                        } else {
+                            // Left unacked, the session fails at the sender
+                            self.corrupted_sessions.insert(source, session_id);
+                        }
+                        return;
+                    }
+                    Err(err) => {
+                        warn!("Client {}: {}", self.id, err);
                     }
-                }
-                Err(err) => {
-                    warn!("Client {}: {}", self.id, err);
                 }
             }
+            FragmentReception::Incomplete => {}
+            FragmentReception::Duplicate => {
+                info!(
+                    "Client {}: Duplicate fragment {} of session {}, acknowledging again",
+                    self.id, fragment_index, session_id
+                );
+            }
+            FragmentReception::AlreadyDelivered => {
+                info!(
+                    "Client {}: Late fragment {} of already delivered session {}, acknowledging again",
+                    self.id, fragment_index, session_id
+                );
+            }
+            FragmentReception::Corrupted => {
+                info!(
+                    "Client {}: Fragment {} of corrupted session {}, not acknowledging it",
+                    self.id, fragment_index, session_id
+                );
+                return;
+            }
+            FragmentReception::Invalid(reason) => {
+                warn!(
+                    "Client {}: Rejecting fragment {} of session {}: {}",
+                    self.id, fragment_index, session_id, reason
+                );
+                // The sender resends the session, and gives it up once the
+                // fragment is rejected more often than its retry budget
+                self.send_response_packet(
+                    PacketType::Nack(Nack {
+                        fragment_index,
+                        nack_type: NackType::Dropped,
+                    }),
+                    session_id,
+                    source_routing_header,
+                );
+                return;
+            }
         }
 
-        let fragment_index = fragment.fragment_index;
-
         // Send an Acknowledgment
-        let ack = Ack { fragment_index };
+        self.send_response_packet(
+            PacketType::Ack(Ack { fragment_index }),
+            session_id,
+            source_routing_header,
+        );
+    }
+
+    /// Sends an Ack or a Nack back to the source of a fragment, reversing its route.
+    /// Falls back to the SC shortcut if the first hop is not reachable.
+    ///
+    /// ### Arguments
+    /// * `pack_type` - The Ack or Nack to be sent
+    /// * `session_id` - The ID of the message session
+    /// * `source_routing_header` - Routing header of the received fragment
+    fn send_response_packet(
+        &mut self,
+        pack_type: PacketType,
+        session_id: u64,
+        source_routing_header: &SourceRoutingHeader,
+    ) {
+        let (fragment_index, header_type) = match &pack_type {
+            PacketType::Ack(ack) => (ack.fragment_index, PacketTypeHeader::Ack),
+            PacketType::Nack(nack) => (nack.fragment_index, PacketTypeHeader::Nack),
+            _ => unreachable!("Client {}: Invalid response packet type", self.id),
+        };
 
-        let ack_packet = Packet {
-            pack_type: PacketType::Ack(ack),
+        let response_packet = Packet {
+            pack_type,
             routing_header: SourceRoutingHeader {
                 hop_index: 1,
                 hops: source_routing_header
@@ -75,57 +203,109 @@ impl RustbustersClient {
             session_id,
         };
 
-        // Send the Acknowledgment back to the sender
-        let next_hop = ack_packet.routing_header.hops[1];
+        // Send the response back to the sender
+        let next_hop = response_packet.routing_header.hops[1];
 
         if let Some(sender) = self.packet_send.get(&next_hop) {
-            if let Err(err) = sender.send(ack_packet.clone()) {
+            if let Err(err) = sender.send(response_packet.clone()) {
                 warn!(
-                    "Client {}: Error sending Ack for fragment {} to {}: {}",
-                    self.id, fragment_index, next_hop, err
+                    "Client {}: Error sending {:?} for fragment {} to {}: {}",
+                    self.id, header_type, fragment_index, next_hop, err
                 );
-                self.send_to_sc(ControllerShortcut(ack_packet.clone()));
-                info!("Client {}: Sending ack through SC", self.id);
+                self.send_to_sc(ControllerShortcut(response_packet.clone()));
+                info!("Client {}: Sending {:?} through SC", self.id, header_type);
             } else {
                 info!(
-                    "Client {}: Sent Ack for fragment {} to {}",
-                    self.id, fragment_index, next_hop
+                    "Client {}: Sent {:?} for fragment {} to {}",
+                    self.id, header_type, fragment_index, next_hop
                 );
             }
         } else {
             warn!(
-                "Client {}: Cannot send Ack for fragment {} to {}",
-                self.id, fragment_index, next_hop
+                "Client {}: Cannot send {:?} for fragment {} to {}",
+                self.id, header_type, fragment_index, next_hop
             );
-            self.send_to_sc(ControllerShortcut(ack_packet.clone()));
+            self.send_to_sc(ControllerShortcut(response_packet.clone()));
         }
         self.send_to_sc(PacketSent(PacketHeader {
             session_id,
-            pack_type: PacketTypeHeader::Ack,
-            routing_header: ack_packet.routing_header.clone(),
+            pack_type: header_type,
+            routing_header: response_packet.routing_header.clone(),
         }));
     }
 
     /// Stores a fragment in the pending received collection.
     ///
     /// ### Arguments
+    /// * `source` - The node that sent the session
     /// * `session_id` - The ID of the message session
     /// * `fragment` - The fragment to store
     ///
     /// ### Returns
-    /// `true` if all fragments for the session have been received, `false` otherwise
-    fn set_pending(&mut self, session_id: u64, fragment: Fragment) -> bool {
+    /// The outcome of the reception, `FragmentReception::Complete` if all
+    /// fragments for the session have been received
+    fn set_pending(
+        &mut self,
+        source: NodeId,
+        session_id: u64,
+        fragment: Fragment,
+    ) -> FragmentReception {
+        if self.completed_sessions.contains(source, session_id) {
+            return FragmentReception::AlreadyDelivered;
+        }
+        if self.corrupted_sessions.contains(source, session_id) {
+            return FragmentReception::Corrupted;
+        }
+
         let fragment_index = fragment.fragment_index;
         let total_n_fragments = fragment.total_n_fragments;
+
+        if fragment_index >= total_n_fragments {
+            return FragmentReception::Invalid(format!(
+                "fragment index {fragment_index} out of range for {total_n_fragments} fragments"
+            ));
+        }
+        if total_n_fragments > self.reassembly_config.max_fragments {
+            return FragmentReception::Invalid(format!(
+                "{total_n_fragments} fragments exceed the limit of {}",
+                self.reassembly_config.max_fragments
+            ));
+        }
+        if fragment.length as usize > FRAGMENT_DSIZE {
+            return FragmentReception::Invalid(format!(
+                "fragment length {} exceeds {FRAGMENT_DSIZE} bytes",
+                fragment.length
+            ));
+        }
+
         // insert the fragment in the pending_received map at index fragment_index
-        let fragments = vec![None; fragment.total_n_fragments as usize];
+        let fragments = vec![None; total_n_fragments as usize];
         let entry = self
             .pending_received
             .entry(session_id)
             .or_insert((fragments, 0));
+
+        if entry.0.len() as u64 != total_n_fragments {
+            return FragmentReception::Invalid(format!(
+                "total_n_fragments {} does not match the {} of the session",
+                total_n_fragments,
+                entry.0.len()
+            ));
+        }
+        if entry.0[fragment_index as usize].is_some() {
+            return FragmentReception::Duplicate;
+        }
+
         entry.0[fragment_index as usize] = Some(fragment);
         entry.1 += 1;
+        let complete = entry.1 == total_n_fragments;
 
-        entry.1 == total_n_fragments
+        self.touch_reassembly_session(session_id);
+
+        if complete {
+            FragmentReception::Complete
+        } else {
+            FragmentReception::Incomplete
+        }
     }
 }
@@ -1,7 +1,9 @@
+use crate::client::events::ClientEvent;
 use crate::client::RustbustersClient;
-use common_utils::{HostEvent, PacketHeader, PacketTypeHeader};
+use common_utils::{HostEvent, PacketHeader, PacketTypeHeader, ServerToClientMessage};
+use crossbeam_channel::Sender;
 use log::{info, warn};
-use wg_2024::network::SourceRoutingHeader;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::{NackType, Packet};
 
 impl RustbustersClient {
@@ -17,19 +19,40 @@ impl RustbustersClient {
     /// * `fragment_index` - The index of the fragment that failed
     /// * `nack_type` - The type of failure that occurred
     /// * `nack_header` - The source routing header of the NACK
+    /// * `ws_to_ui_sender` - Channel to report a failed session to the UI
     pub(crate) fn handle_nack(
         &mut self,
         session_id: u64,
         fragment_index: u64,
         nack_type: NackType,
         nack_header: &SourceRoutingHeader,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
     ) {
+        if let Some((destination, _, _)) = self.pending_session_info.get(&session_id) {
+            self.metrics.nack_received(*destination, &nack_type);
+        }
+
+        // Sent by the receiver itself, which wants the session again: the
+        // route did not lose the fragment, so it is not charged for it
+        if matches!(nack_type, NackType::Dropped)
+            && self
+                .pending_session_info
+                .get(&session_id)
+                .is_some_and(|(destination, _, _)| nack_header.hops.first() == Some(destination))
+        {
+            self.resend_session(session_id, fragment_index, ws_to_ui_sender);
+            return;
+        }
+
         match self.pending_sent.get(&(session_id, fragment_index)).cloned() {
             Some(mut packet) => {
                 match nack_type {
                     NackType::Dropped => {
                         info!("Client {}: Resending fragment {}", self.id, fragment_index);
                         self.update_edge_stats_on_nack(&nack_header.hops);
+                        if let Some(destination) = packet.routing_header.hops.last() {
+                            self.on_fragment_dropped(*destination);
+                        }
                         
                         // Check conditions for dropped packets
                         let drop_from = nack_header.hops[0];
@@ -41,6 +64,9 @@ impl RustbustersClient {
                         };
                         
                         self.reroute_and_resend(&mut packet, fragment_index, should_reroute);
+                        self.pending_sent
+                            .insert((session_id, fragment_index), packet);
+                        self.restart_retransmission_timer(session_id, fragment_index);
                     }
                     NackType::ErrorInRouting(drone) => {
                         warn!(
@@ -51,7 +77,12 @@ impl RustbustersClient {
                         self.edge_stats
                             .retain(|(from, to), _| *from != drone && *to != drone);
                         self.known_nodes.lock().unwrap().remove(&drone);
+                        self.topology_ages.nodes.remove(&drone);
+                        self.topology_changed("drone removed after ErrorInRouting");
                         self.reroute_and_resend(&mut packet, fragment_index, true);
+                        self.pending_sent
+                            .insert((session_id, fragment_index), packet);
+                        self.restart_retransmission_timer(session_id, fragment_index);
                     }
                     NackType::DestinationIsDrone | NackType::UnexpectedRecipient(_) => {
                         warn!(
@@ -67,6 +98,69 @@ impl RustbustersClient {
         }
     }
 
+    /// Sends every fragment of a session again, as asked by its receiver.
+    /// Each request consumes a retry of the nacked fragment, so that a
+    /// receiver that keeps rejecting the session makes it fail.
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the message session
+    /// * `fragment_index` - The index of the nacked fragment
+    /// * `ws_to_ui_sender` - Channel to report a failed session to the UI
+    fn resend_session(
+        &mut self,
+        session_id: u64,
+        fragment_index: u64,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        if !self.charge_retransmission(session_id, fragment_index) {
+            self.fail_session(
+                session_id,
+                "the receiver kept rejecting the message",
+                ws_to_ui_sender,
+            );
+            return;
+        }
+        info!(
+            "Client {}: Receiver requested session {} again",
+            self.id, session_id
+        );
+
+        let acked: Vec<(u64, u64)> = self
+            .acked_fragments
+            .keys()
+            .filter(|(key, _)| *key == session_id)
+            .copied()
+            .collect();
+        for key in acked {
+            if let Some(packet) = self.acked_fragments.remove(&key) {
+                if let Some(destination) = packet.routing_header.hops.last() {
+                    self.fragment_in_flight(*destination);
+                }
+                self.pending_sent.insert(key, packet);
+            }
+        }
+
+        let mut fragments: Vec<(u64, u64)> = self
+            .pending_sent
+            .keys()
+            .filter(|(key, _)| *key == session_id)
+            .copied()
+            .collect();
+        fragments.sort_unstable();
+        for (session_id, fragment_index) in fragments {
+            if let Some(mut packet) = self
+                .pending_sent
+                .get(&(session_id, fragment_index))
+                .cloned()
+            {
+                self.reroute_and_resend(&mut packet, fragment_index, false);
+                self.pending_sent
+                    .insert((session_id, fragment_index), packet);
+                self.restart_retransmission_timer(session_id, fragment_index);
+            }
+        }
+    }
+
     /// Attempts to reroute and resend a packet after a failure.
     ///
     /// This function handles both dropped packets and routing errors by:
@@ -78,7 +172,7 @@ impl RustbustersClient {
     /// * `packet` - The packet to be rerouted and resent
     /// * `fragment_index` - The index of the fragment
     /// * `force_reroute` - Whether to force rerouting
-    fn reroute_and_resend(
+    pub(crate) fn reroute_and_resend(
         &mut self,
         packet: &mut Packet,
         fragment_index: u64,
@@ -87,6 +181,8 @@ impl RustbustersClient {
         let destination = *packet.routing_header.hops.last().unwrap();
         
         if force_reroute {
+            // The cached route is likely the one that just failed
+            self.forget_cached_route(destination);
             if let Some(new_path) = self.find_weighted_path(destination) {
                 if new_path != packet.routing_header.hops {
                     info!(
@@ -96,6 +192,14 @@ impl RustbustersClient {
 
                     packet.routing_header.hops = new_path;
                     packet.routing_header.hop_index = 1;
+                    self.record_event(ClientEvent::FragmentRerouted {
+                        session_id: packet.session_id,
+                        fragment_index,
+                        destination,
+                        route: packet.routing_header.hops.clone(),
+                    });
+                    self.metrics.fragment_rerouted(destination);
+                    self.reroute_session_message(packet.session_id, destination);
                 }
             }
         }
@@ -108,6 +212,7 @@ impl RustbustersClient {
                     self.id, fragment_index, err
                 );
             } else {
+                self.metrics.fragment_resent(destination);
                 self.send_to_sc(HostEvent::PacketSent(PacketHeader {
                     session_id: packet.session_id,
                     pack_type: PacketTypeHeader::MsgFragment,
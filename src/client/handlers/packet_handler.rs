@@ -24,6 +24,8 @@ impl RustbustersClient {
         packet: Packet,
         ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
     ) {
+        self.metrics.runtime.packet_received(&packet.pack_type);
+
         match packet.pack_type {
             PacketType::FloodRequest(flood_request) => {
                 info!(
@@ -59,6 +61,8 @@ impl RustbustersClient {
                     self.id, ack.fragment_index
                 );
                 self.handle_ack(packet.session_id, ack.fragment_index);
+                // The Ack may have made room in the congestion window
+                self.release_all_queued_fragments();
             }
             PacketType::Nack(nack) => {
                 // Handle Negative Acknowledgments
@@ -68,7 +72,9 @@ impl RustbustersClient {
                     nack.fragment_index,
                     nack.nack_type,
                     &packet.routing_header,
+                    ui_sender,
                 );
+                self.release_all_queued_fragments();
             }
         }
     }
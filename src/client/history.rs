@@ -0,0 +1,211 @@
+use crate::client::RustbustersClient;
+use common_utils::{ClientToServerMessage, ServerToClientMessage};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use wg_2024::network::NodeId;
+
+const DEFAULT_HISTORY_DIR: &str = "client-history";
+
+/// Location of the message history of the clients
+#[derive(Debug, Clone)]
+pub struct HistoryConfig {
+    /// When disabled, the default, the messages are only forwarded to the UI
+    pub enabled: bool,
+    /// Directory holding one append-only log per client
+    pub dir: PathBuf,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from(DEFAULT_HISTORY_DIR),
+        }
+    }
+}
+
+/// A recorded message, tagged by its direction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "direction", content = "message", rename_all = "snake_case")]
+pub enum HistoryMessage {
+    Sent(ClientToServerMessage),
+    Received(ServerToClientMessage),
+}
+
+/// A line of the history log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Position of the entry in the log of the client
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    pub server_id: NodeId,
+    /// The other end of the conversation: the other client for chat
+    /// messages, the server otherwise
+    pub peer_id: NodeId,
+    #[serde(flatten)]
+    pub message: HistoryMessage,
+}
+
+/// Append-only log of the messages of a client, fully indexed in memory
+#[derive(Debug)]
+pub(crate) struct HistoryStore {
+    file: File,
+    entries: Vec<HistoryEntry>,
+}
+
+impl HistoryStore {
+    /// Opens the log of a client, loading the entries already recorded
+    ///
+    /// ### Arguments
+    /// * `dir` - Directory of the logs
+    /// * `client_id` - The ID of the client
+    pub(crate) fn open(dir: &Path, client_id: NodeId) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("client-{client_id}.jsonl"));
+
+        let mut entries = Vec::new();
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                let line = line?;
+                match serde_json::from_str::<HistoryEntry>(&line) {
+                    Ok(entry) => entries.push(entry),
+                    // A torn last line is left by a crash in the middle of a write
+                    Err(err) => warn!("Client {client_id}: Skipping history line: {err}"),
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { file, entries })
+    }
+
+    /// Appends a message to the log
+    ///
+    /// ### Arguments
+    /// * `server_id` - The server the message went through
+    /// * `peer_id` - The other end of the conversation
+    /// * `message` - The message
+    pub(crate) fn append(
+        &mut self,
+        server_id: NodeId,
+        peer_id: NodeId,
+        message: HistoryMessage,
+    ) -> io::Result<()> {
+        let entry = HistoryEntry {
+            seq: self.entries.last().map_or(0, |last| last.seq + 1),
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            server_id,
+            peer_id,
+            message,
+        };
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Returns a page of the log, oldest entry first
+    ///
+    /// ### Arguments
+    /// * `peer_id` - Only the entries of this conversation, if given
+    /// * `before` - Only the entries older than this sequence number, if given
+    /// * `limit` - Maximum number of entries, the most recent ones are kept
+    pub(crate) fn page(
+        &self,
+        peer_id: Option<NodeId>,
+        before: Option<u64>,
+        limit: usize,
+    ) -> Vec<HistoryEntry> {
+        let mut page: Vec<HistoryEntry> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| before.is_none_or(|before| entry.seq < before))
+            .filter(|entry| peer_id.is_none_or(|peer_id| entry.peer_id == peer_id))
+            .take(limit)
+            .cloned()
+            .collect();
+        page.reverse();
+        page
+    }
+}
+
+impl RustbustersClient {
+    /// Opens the history log of the client, if enabled
+    pub(crate) fn open_history(&mut self) {
+        if !self.history_config.enabled || self.history.is_some() {
+            return;
+        }
+
+        match HistoryStore::open(&self.history_config.dir, self.id) {
+            Ok(store) => {
+                info!(
+                    "Client {}: Opened history with {} entries",
+                    self.id,
+                    store.entries.len()
+                );
+                self.history = Some(Arc::new(Mutex::new(store)));
+            }
+            Err(err) => warn!("Client {}: Unable to open history: {}", self.id, err),
+        }
+    }
+
+    /// Records a chat message sent by this client
+    ///
+    /// ### Arguments
+    /// * `server_id` - The server the message is sent to
+    /// * `message` - The message
+    pub(crate) fn record_sent_message(&self, server_id: NodeId, message: &ClientToServerMessage) {
+        if let ClientToServerMessage::SendPrivateMessage { recipient_id, .. } = message {
+            self.record_history(
+                server_id,
+                *recipient_id,
+                HistoryMessage::Sent(message.clone()),
+            );
+        }
+    }
+
+    /// Records a message received from a server
+    ///
+    /// ### Arguments
+    /// * `server_id` - The server the message comes from
+    /// * `message` - The message
+    pub(crate) fn record_received_message(
+        &self,
+        server_id: NodeId,
+        message: &ServerToClientMessage,
+    ) {
+        let peer_id = match message {
+            ServerToClientMessage::SendingError {
+                message: ClientToServerMessage::SendPrivateMessage { recipient_id, .. },
+                ..
+            } => *recipient_id,
+            ServerToClientMessage::PrivateMessage { message, .. } => message.sender_id,
+            _ => server_id,
+        };
+        self.record_history(
+            server_id,
+            peer_id,
+            HistoryMessage::Received(message.clone()),
+        );
+    }
+
+    fn record_history(&self, server_id: NodeId, peer_id: NodeId, message: HistoryMessage) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        if let Err(err) = history.lock().unwrap().append(server_id, peer_id, message) {
+            warn!("Client {}: Unable to record history: {}", self.id, err);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Serialize;
+use std::time::Duration;
+
+/// Upper bounds of the latency buckets, in seconds
+pub(crate) const LATENCY_BUCKETS: [f64; 11] =
+    [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Histogram of message latencies with fixed buckets
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyHistogram {
+    /// Upper bound of every bucket, in seconds
+    pub bounds: Vec<f64>,
+    /// Number of observations of every bucket, the last one counting those above all bounds
+    pub counts: Vec<u64>,
+    /// Sum of all observations, in seconds
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            bounds: LATENCY_BUCKETS.to_vec(),
+            counts: vec![0; LATENCY_BUCKETS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub(crate) fn observe(&mut self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    /// Cumulative counts per bucket, as expected by Prometheus, the last one being `+Inf`
+    pub fn cumulative_counts(&self) -> Vec<u64> {
+        self.counts
+            .iter()
+            .scan(0, |total, count| {
+                *total += count;
+                Some(*total)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,197 @@
+mod histogram;
+pub(crate) mod prometheus;
+mod runtime;
+
+pub use histogram::LatencyHistogram;
+pub use runtime::{PacketCounts, RuntimeMetrics};
+
+use crate::client::metrics::prometheus::{render, MetricFamily, MetricKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NackType;
+
+/// Nacks received, by type
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NackCounts {
+    pub error_in_routing: u64,
+    pub destination_is_drone: u64,
+    pub dropped: u64,
+    pub unexpected_recipient: u64,
+}
+
+/// Delivery metrics of the sessions sent to a single destination
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DestinationMetrics {
+    /// Time between sending a message and receiving the Ack of its last fragment
+    pub latency: LatencyHistogram,
+    /// Fragments sent for the first time
+    pub fragments_sent: u64,
+    /// Fragments sent again after a Nack or an Ack timeout
+    pub fragments_resent: u64,
+    pub nacks: NackCounts,
+    /// Resent fragments that were moved to a different route
+    pub reroutes: u64,
+    /// Payload bytes of the messages fully acknowledged, retransmissions excluded
+    pub goodput_bytes: u64,
+    pub messages_delivered: u64,
+    pub messages_failed: u64,
+}
+
+/// Per-destination delivery metrics of a client, and its runtime counters and gauges
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ClientMetrics {
+    pub destinations: BTreeMap<NodeId, DestinationMetrics>,
+    pub runtime: RuntimeMetrics,
+    // session_id -> payload bytes of the sessions in flight
+    #[serde(skip)]
+    session_bytes: HashMap<u64, u64>,
+}
+
+impl ClientMetrics {
+    fn destination(&mut self, destination: NodeId) -> &mut DestinationMetrics {
+        self.destinations.entry(destination).or_default()
+    }
+
+    pub(crate) fn session_started(&mut self, session_id: u64, payload_bytes: u64) {
+        self.session_bytes.insert(session_id, payload_bytes);
+    }
+
+    pub(crate) fn session_delivered(
+        &mut self,
+        session_id: u64,
+        destination: NodeId,
+        latency: Duration,
+    ) {
+        let bytes = self.session_bytes.remove(&session_id).unwrap_or(0);
+        let metrics = self.destination(destination);
+        metrics.latency.observe(latency);
+        metrics.goodput_bytes += bytes;
+        metrics.messages_delivered += 1;
+    }
+
+    pub(crate) fn session_failed(&mut self, session_id: u64, destination: NodeId) {
+        self.session_bytes.remove(&session_id);
+        self.destination(destination).messages_failed += 1;
+    }
+
+    pub(crate) fn fragment_sent(&mut self, destination: NodeId) {
+        self.destination(destination).fragments_sent += 1;
+    }
+
+    pub(crate) fn fragment_resent(&mut self, destination: NodeId) {
+        self.destination(destination).fragments_resent += 1;
+    }
+
+    pub(crate) fn fragment_rerouted(&mut self, destination: NodeId) {
+        self.destination(destination).reroutes += 1;
+    }
+
+    pub(crate) fn nack_received(&mut self, destination: NodeId, nack_type: &NackType) {
+        let nacks = &mut self.destination(destination).nacks;
+        match nack_type {
+            NackType::ErrorInRouting(_) => nacks.error_in_routing += 1,
+            NackType::DestinationIsDrone => nacks.destination_is_drone += 1,
+            NackType::Dropped => nacks.dropped += 1,
+            NackType::UnexpectedRecipient(_) => nacks.unexpected_recipient += 1,
+        }
+    }
+
+    /// Metric families of this client, labelled with its ID and the destination
+    pub(crate) fn prometheus_families(&self, client_id: NodeId) -> Vec<MetricFamily> {
+        let mut latency = MetricFamily::new(
+            "rustbusters_client_message_latency_seconds",
+            "Time between sending a message and the Ack of its last fragment",
+            MetricKind::Histogram,
+        );
+        let mut sent = MetricFamily::new(
+            "rustbusters_client_fragments_sent_total",
+            "Fragments sent for the first time",
+            MetricKind::Counter,
+        );
+        let mut resent = MetricFamily::new(
+            "rustbusters_client_fragments_resent_total",
+            "Fragments sent again after a Nack or an Ack timeout",
+            MetricKind::Counter,
+        );
+        let mut nacks = MetricFamily::new(
+            "rustbusters_client_nacks_total",
+            "Nacks received, by type",
+            MetricKind::Counter,
+        );
+        let mut reroutes = MetricFamily::new(
+            "rustbusters_client_reroutes_total",
+            "Resent fragments moved to a different route",
+            MetricKind::Counter,
+        );
+        let mut goodput = MetricFamily::new(
+            "rustbusters_client_goodput_bytes_total",
+            "Payload bytes of fully acknowledged messages",
+            MetricKind::Counter,
+        );
+        let mut delivered = MetricFamily::new(
+            "rustbusters_client_messages_delivered_total",
+            "Messages whose fragments were all acknowledged",
+            MetricKind::Counter,
+        );
+        let mut failed = MetricFamily::new(
+            "rustbusters_client_messages_failed_total",
+            "Messages given up after exhausting the retry budget",
+            MetricKind::Counter,
+        );
+
+        for (destination, metrics) in &self.destinations {
+            let labels = vec![
+                ("client", client_id.to_string()),
+                ("destination", destination.to_string()),
+            ];
+
+            let cumulative = metrics.latency.cumulative_counts();
+            for (bound, count) in metrics.latency.bounds.iter().zip(&cumulative) {
+                let mut bucket_labels = labels.clone();
+                bucket_labels.push(("le", bound.to_string()));
+                latency.suffixed_sample("_bucket", bucket_labels, *count as f64);
+            }
+            let mut inf_labels = labels.clone();
+            inf_labels.push(("le", "+Inf".to_string()));
+            latency.suffixed_sample("_bucket", inf_labels, metrics.latency.count as f64);
+            latency.suffixed_sample("_sum", labels.clone(), metrics.latency.sum);
+            latency.suffixed_sample("_count", labels.clone(), metrics.latency.count as f64);
+
+            sent.sample(labels.clone(), metrics.fragments_sent as f64);
+            resent.sample(labels.clone(), metrics.fragments_resent as f64);
+            for (nack_type, count) in [
+                ("error_in_routing", metrics.nacks.error_in_routing),
+                ("destination_is_drone", metrics.nacks.destination_is_drone),
+                ("dropped", metrics.nacks.dropped),
+                ("unexpected_recipient", metrics.nacks.unexpected_recipient),
+            ] {
+                let mut nack_labels = labels.clone();
+                nack_labels.push(("type", nack_type.to_string()));
+                nacks.sample(nack_labels, count as f64);
+            }
+            reroutes.sample(labels.clone(), metrics.reroutes as f64);
+            goodput.sample(labels.clone(), metrics.goodput_bytes as f64);
+            delivered.sample(labels.clone(), metrics.messages_delivered as f64);
+            failed.sample(labels, metrics.messages_failed as f64);
+        }
+
+        let mut families = vec![
+            latency, sent, resent, nacks, reroutes, goodput, delivered, failed,
+        ];
+        families.extend(
+            self.runtime
+                .prometheus_families(&vec![("client", client_id.to_string())]),
+        );
+        families
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format
+    ///
+    /// ### Arguments
+    /// * `client_id` - The ID of the client, used as a label
+    pub fn to_prometheus(&self, client_id: NodeId) -> String {
+        render(&self.prometheus_families(client_id))
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt::Write;
+
+/// Labels of a sample, as (name, value)
+pub(crate) type Labels = Vec<(&'static str, String)>;
+
+/// Type of a Prometheus metric family
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+/// All the samples of a metric, rendered under a single HELP/TYPE header
+#[derive(Debug, Clone)]
+pub(crate) struct MetricFamily {
+    pub(crate) name: &'static str,
+    pub(crate) help: &'static str,
+    pub(crate) kind: MetricKind,
+    /// (suffix of the name, labels, value)
+    pub(crate) samples: Vec<(&'static str, Labels, f64)>,
+}
+
+impl MetricFamily {
+    pub(crate) fn new(name: &'static str, help: &'static str, kind: MetricKind) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            samples: Vec::new(),
+        }
+    }
+
+    pub(crate) fn sample(&mut self, labels: Labels, value: f64) {
+        self.samples.push(("", labels, value));
+    }
+
+    pub(crate) fn suffixed_sample(&mut self, suffix: &'static str, labels: Labels, value: f64) {
+        self.samples.push((suffix, labels, value));
+    }
+}
+
+/// Merges the families of several clients, so that every metric is
+/// rendered under a single HELP/TYPE header
+///
+/// ### Arguments
+/// * `families` - The families of every client, in order
+pub(crate) fn merge_families(families: Vec<Vec<MetricFamily>>) -> Vec<MetricFamily> {
+    let mut merged: Vec<MetricFamily> = Vec::new();
+    for family in families.into_iter().flatten() {
+        match merged.iter_mut().find(|known| known.name == family.name) {
+            Some(known) => known.samples.extend(family.samples),
+            None => merged.push(family),
+        }
+    }
+    merged
+}
+
+/// Renders metric families in the Prometheus text exposition format
+pub(crate) fn render(families: &[MetricFamily]) -> String {
+    let mut out = String::new();
+    for family in families {
+        let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+        let _ = writeln!(out, "# TYPE {} {}", family.name, family.kind.as_str());
+        for (suffix, labels, value) in &family.samples {
+            let labels = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            if labels.is_empty() {
+                let _ = writeln!(out, "{}{suffix} {value}", family.name);
+            } else {
+                let _ = writeln!(out, "{}{suffix}{{{labels}}} {value}", family.name);
+            }
+        }
+    }
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,133 @@
+use crate::client::metrics::prometheus::{Labels, MetricFamily, MetricKind};
+use crate::client::routing::RouteCacheStats;
+use serde::Serialize;
+use wg_2024::packet::PacketType;
+
+/// Packets received, by type
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PacketCounts {
+    pub msg_fragment: u64,
+    pub ack: u64,
+    pub nack: u64,
+    pub flood_request: u64,
+    pub flood_response: u64,
+}
+
+/// Counters and gauges of the client as a whole, independent of the destination
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RuntimeMetrics {
+    pub packets_received: PacketCounts,
+    /// Fragments sent and not yet acknowledged
+    pub pending_sent: usize,
+    /// Sessions being reassembled
+    pub pending_received: usize,
+    pub known_nodes: usize,
+    /// Discovery floods started by this client
+    pub discovery_rounds: u64,
+    /// Messages from the UI forwarded to a server
+    pub ui_messages_forwarded: u64,
+    pub route_cache: RouteCacheStats,
+}
+
+impl RuntimeMetrics {
+    pub(crate) fn packet_received(&mut self, pack_type: &PacketType) {
+        let counts = &mut self.packets_received;
+        match pack_type {
+            PacketType::MsgFragment(_) => counts.msg_fragment += 1,
+            PacketType::Ack(_) => counts.ack += 1,
+            PacketType::Nack(_) => counts.nack += 1,
+            PacketType::FloodRequest(_) => counts.flood_request += 1,
+            PacketType::FloodResponse(_) => counts.flood_response += 1,
+        }
+    }
+
+    /// Metric families of the runtime metrics
+    ///
+    /// ### Arguments
+    /// * `labels` - The labels identifying the client
+    pub(crate) fn prometheus_families(&self, labels: &Labels) -> Vec<MetricFamily> {
+        let mut received = MetricFamily::new(
+            "rustbusters_client_packets_received_total",
+            "Packets received, by type",
+            MetricKind::Counter,
+        );
+        for (packet_type, count) in [
+            ("msg_fragment", self.packets_received.msg_fragment),
+            ("ack", self.packets_received.ack),
+            ("nack", self.packets_received.nack),
+            ("flood_request", self.packets_received.flood_request),
+            ("flood_response", self.packets_received.flood_response),
+        ] {
+            let mut type_labels = labels.clone();
+            type_labels.push(("type", packet_type.to_string()));
+            received.sample(type_labels, count as f64);
+        }
+
+        let mut pending_sent = MetricFamily::new(
+            "rustbusters_client_pending_sent_fragments",
+            "Fragments sent and not yet acknowledged",
+            MetricKind::Gauge,
+        );
+        pending_sent.sample(labels.clone(), self.pending_sent as f64);
+
+        let mut pending_received = MetricFamily::new(
+            "rustbusters_client_pending_received_sessions",
+            "Sessions being reassembled",
+            MetricKind::Gauge,
+        );
+        pending_received.sample(labels.clone(), self.pending_received as f64);
+
+        let mut known_nodes = MetricFamily::new(
+            "rustbusters_client_known_nodes",
+            "Nodes known to the client",
+            MetricKind::Gauge,
+        );
+        known_nodes.sample(labels.clone(), self.known_nodes as f64);
+
+        let mut discovery_rounds = MetricFamily::new(
+            "rustbusters_client_discovery_rounds_total",
+            "Discovery floods started by the client",
+            MetricKind::Counter,
+        );
+        discovery_rounds.sample(labels.clone(), self.discovery_rounds as f64);
+
+        let mut ui_forwarded = MetricFamily::new(
+            "rustbusters_client_ui_messages_forwarded_total",
+            "Messages from the UI forwarded to a server",
+            MetricKind::Counter,
+        );
+        ui_forwarded.sample(labels.clone(), self.ui_messages_forwarded as f64);
+
+        let mut route_cache_lookups = MetricFamily::new(
+            "rustbusters_client_route_cache_lookups_total",
+            "Route lookups, by whether the route cache answered them",
+            MetricKind::Counter,
+        );
+        for (result, count) in [
+            ("hit", self.route_cache.hits),
+            ("miss", self.route_cache.misses),
+        ] {
+            let mut result_labels = labels.clone();
+            result_labels.push(("result", result.to_string()));
+            route_cache_lookups.sample(result_labels, count as f64);
+        }
+
+        let mut route_cache_invalidations = MetricFamily::new(
+            "rustbusters_client_route_cache_invalidations_total",
+            "Times the whole route cache was dropped",
+            MetricKind::Counter,
+        );
+        route_cache_invalidations.sample(labels.clone(), self.route_cache.invalidations as f64);
+
+        vec![
+            received,
+            pending_sent,
+            pending_received,
+            known_nodes,
+            discovery_rounds,
+            ui_forwarded,
+            route_cache_lookups,
+            route_cache_invalidations,
+        ]
+    }
+}
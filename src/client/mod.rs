@@ -1,24 +1,60 @@
-mod commands;
-mod fragmentation;
+pub(crate) mod commands;
+mod congestion;
+mod events;
+pub(crate) mod fragmentation;
 mod handlers;
+pub(crate) mod history;
+pub(crate) mod metrics;
+pub(crate) mod outbox;
 mod packet_sender;
+mod retransmission;
 pub(crate) mod routing;
 mod ui_connector;
 
+pub use commands::{CommandError, CommandResult};
+pub use congestion::CongestionConfig;
+pub use events::{ClientEvent, EventJournalConfig, EventJournalReader, JournalEntry};
+pub use fragmentation::{FramingConfig, PayloadCodec, ReassemblyConfig};
+pub use history::{HistoryConfig, HistoryEntry, HistoryMessage};
+pub use metrics::{
+    ClientMetrics, DestinationMetrics, LatencyHistogram, NackCounts, PacketCounts, RuntimeMetrics,
+};
+pub use outbox::{MessageStatus, MessageStatusSnapshot, OutboxConfig, OutboxSnapshot};
+pub use retransmission::RetransmissionConfig;
+pub use routing::{
+    EdgeSnapshot, EdgeStatsSnapshot, FloodTrackerConfig, MultipathConfig, NodeSnapshot,
+    RouteCacheConfig, RouteCacheStats, TopologyAgingConfig, TopologySnapshot,
+};
+pub use ui_connector::{DeliveryUpdate, MessageSender, OutgoingMessage, UiEndpoint};
+
+use crate::client::commands::COMMAND_RESULTS_CAPACITY;
+use crate::client::congestion::CongestionWindow;
+use crate::client::events::EventJournal;
+use crate::client::fragmentation::{
+    CompletedSessions, CompressionStats, PayloadEncoding, ReassemblyTimestamps,
+    DEFAULT_COMPLETED_SESSIONS_CAPACITY,
+};
+use crate::client::history::HistoryStore;
+use crate::client::outbox::Outbox;
+use crate::client::retransmission::RetransmissionTimer;
 use crate::client::routing::edge_stats::EdgeStats;
-use crate::ui::CLIENTS_STATE;
-use common_utils::{HostCommand, HostEvent, HostMessage};
-use crossbeam_channel::{select_biased, Receiver, Sender};
+use crate::client::routing::{FloodTracker, RouteCache, TopologyAges};
+use crate::ui::{unregister_client, UiConfig};
+use common_utils::{HostCommand, HostEvent, HostMessage, ServerToClientMessage};
+use crossbeam_channel::{never, select_biased, Receiver, Sender};
 use log::{error, info};
 use petgraph::prelude::GraphMap;
 use petgraph::Undirected;
 use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use wg_2024::network::NodeId;
 use wg_2024::packet::{Fragment, NodeType, Packet};
 
 const DEFAULT_DISCOVERY_INTERVAL: Duration = Duration::from_secs(20);
+/// How often the snapshots read by the HTTP server are refreshed
+const SNAPSHOT_PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct RustbustersClient {
     pub(crate) id: NodeId,
@@ -32,13 +68,79 @@ pub struct RustbustersClient {
     session_id_counter: u64,
     // (session_id, fragment_index) -> packet
     pub(crate) pending_sent: HashMap<(u64, u64), Packet>,
+    // (session_id, fragment_index) -> acked packet of a session still in progress,
+    // kept in case the receiver asks for it again after a checksum mismatch
+    pub(crate) acked_fragments: HashMap<(u64, u64), Packet>,
     // session_id -> time_sent
     pub(crate) pending_session_info: HashMap<u64, (NodeId, HostMessage, Instant)>,
+    // (session_id, fragment_index) -> retransmission timer of the pending fragment
+    pub(crate) retransmission_timers: HashMap<(u64, u64), RetransmissionTimer>,
+    retransmission_config: RetransmissionConfig,
+    // destination -> AIMD send window and fragments waiting for it
+    pub(crate) congestion_windows: HashMap<NodeId, CongestionWindow>,
+    congestion_config: CongestionConfig,
     // session_id -> (fragments, num_fragments) (u8 is the number of fragments received) (for reassembly)
     pub(crate) pending_received: HashMap<u64, (Vec<Option<Fragment>>, u64)>,
-    edge_stats: HashMap<(NodeId, NodeId), EdgeStats>,
+    // session_id -> first/last time a fragment of the session was received
+    reassembly_timestamps: HashMap<u64, ReassemblyTimestamps>,
+    reassembly_config: ReassemblyConfig,
+    last_reassembly_sweep: Instant,
+    // number of incomplete sessions dropped by the reassembly sweep
+    pub(crate) expired_reassemblies: u64,
+    // number of reassembled sessions whose payload checksum did not match
+    pub(crate) integrity_failures: u64,
+    // (source, session_id) of recently delivered sessions, to ignore late duplicates
+    completed_sessions: CompletedSessions,
+    // (source, session_id) of recent sessions that failed verification and
+    // were not asked again, whose fragments are never acked
+    corrupted_sessions: CompletedSessions,
+    framing_config: FramingConfig,
+    // node_id -> payload encoding used by the node in its last message
+    peer_encodings: HashMap<NodeId, PayloadEncoding>,
+    pub(crate) compression_stats: CompressionStats,
+    pub(crate) edge_stats: HashMap<(NodeId, NodeId), EdgeStats>,
+    multipath_config: MultipathConfig,
+    // destination -> best route, dropped whenever the topology changes
+    route_cache: RouteCache,
+    route_cache_config: RouteCacheConfig,
+    // last discovery round and time every node and edge was seen in a flood
+    pub(crate) topology_ages: TopologyAges,
+    topology_aging_config: TopologyAgingConfig,
+    // outstanding and converged discovery floods, and requests answered for other initiators
+    pub(crate) flood_tracker: FloodTracker,
+    flood_tracker_config: FloodTrackerConfig,
     last_discovery: Instant,
     discovery_interval: Duration,
+    // copy of the topology published for the HTTP server
+    pub(crate) shared_topology: Arc<Mutex<TopologySnapshot>>,
+    // per-destination delivery metrics, and their copy published for the HTTP server
+    pub(crate) metrics: ClientMetrics,
+    pub(crate) shared_metrics: Arc<Mutex<ClientMetrics>>,
+    last_snapshot_publish: Instant,
+    pub(crate) ui_config: UiConfig,
+    history_config: HistoryConfig,
+    // opened by run, shared with the HTTP server
+    pub(crate) history: Option<Arc<Mutex<HistoryStore>>>,
+    // shared with the UI, which assigns the IDs of its messages
+    message_ids: Arc<AtomicU64>,
+    // messages waiting for a route, and the delivery status of the recent ones
+    pub(crate) outbox: Outbox,
+    pub(crate) outbox_config: OutboxConfig,
+    pub(crate) shared_outbox: Arc<Mutex<OutboxSnapshot>>,
+    // UI side of the channels below, until taken by the web UI or the embedder
+    ui_endpoint: Option<UiEndpoint>,
+    // NodeId is the destination server
+    ui_requests: Receiver<OutgoingMessage>,
+    // NodeId is the source server
+    ui_events: Sender<(NodeId, ServerToClientMessage)>,
+    pub(crate) delivery_updates: Sender<DeliveryUpdate>,
+    // number of results reported, and the channel they are reported on
+    results_reported: u64,
+    command_results: Sender<CommandResult>,
+    command_results_receiver: Option<Receiver<CommandResult>>,
+    // most recent client events, shared with the HTTP server and the SC
+    pub(crate) journal: Arc<Mutex<EventJournal>>,
+    journal_config: EventJournalConfig,
 }
 
 impl RustbustersClient {
@@ -51,6 +153,12 @@ impl RustbustersClient {
         discovery_interval: Option<Duration>,
     ) -> Self {
         let discovery_interval = discovery_interval.unwrap_or(DEFAULT_DISCOVERY_INTERVAL);
+        let (ui_requests_sender, ui_requests) = crossbeam_channel::unbounded();
+        let (ui_events, ui_events_receiver) = crossbeam_channel::unbounded();
+        let (delivery_updates, delivery_updates_receiver) = crossbeam_channel::unbounded();
+        let (command_results, command_results_receiver) =
+            crossbeam_channel::bounded(COMMAND_RESULTS_CAPACITY);
+        let message_ids = Arc::new(AtomicU64::new(0));
         info!(
             "Client {} spawned successfully with discovery interval {:?}",
             id, discovery_interval
@@ -67,38 +175,204 @@ impl RustbustersClient {
             flood_id_counter: 73,   // arbitrary value
             session_id_counter: 73, // arbitrary value
             pending_sent: HashMap::new(),
+            acked_fragments: HashMap::new(),
             pending_session_info: HashMap::new(),
+            retransmission_timers: HashMap::new(),
+            retransmission_config: RetransmissionConfig::default(),
+            congestion_windows: HashMap::new(),
+            congestion_config: CongestionConfig::default(),
             pending_received: HashMap::new(),
+            reassembly_timestamps: HashMap::new(),
+            reassembly_config: ReassemblyConfig::default(),
+            last_reassembly_sweep: Instant::now(),
+            expired_reassemblies: 0,
+            integrity_failures: 0,
+            completed_sessions: CompletedSessions::new(DEFAULT_COMPLETED_SESSIONS_CAPACITY),
+            corrupted_sessions: CompletedSessions::new(DEFAULT_COMPLETED_SESSIONS_CAPACITY),
+            framing_config: FramingConfig::default(),
+            peer_encodings: HashMap::new(),
+            compression_stats: CompressionStats::default(),
             edge_stats: HashMap::new(),
+            multipath_config: MultipathConfig::default(),
+            route_cache: RouteCache::default(),
+            route_cache_config: RouteCacheConfig::default(),
+            topology_ages: TopologyAges::default(),
+            topology_aging_config: TopologyAgingConfig::default(),
+            flood_tracker: FloodTracker::default(),
+            flood_tracker_config: FloodTrackerConfig::default(),
             last_discovery: Instant::now(),
             discovery_interval,
+            shared_topology: Arc::new(Mutex::new(TopologySnapshot::default())),
+            metrics: ClientMetrics::default(),
+            shared_metrics: Arc::new(Mutex::new(ClientMetrics::default())),
+            last_snapshot_publish: Instant::now(),
+            ui_config: UiConfig::default(),
+            history_config: HistoryConfig::default(),
+            history: None,
+            message_ids: message_ids.clone(),
+            outbox: Outbox::default(),
+            outbox_config: OutboxConfig::default(),
+            shared_outbox: Arc::new(Mutex::new(OutboxSnapshot::default())),
+            ui_endpoint: Some(UiEndpoint {
+                to_client: MessageSender::new(ui_requests_sender, message_ids),
+                from_client: ui_events_receiver,
+                delivery_updates: delivery_updates_receiver,
+            }),
+            ui_requests,
+            ui_events,
+            delivery_updates,
+            results_reported: 0,
+            command_results,
+            command_results_receiver: Some(command_results_receiver),
+            journal: Arc::new(Mutex::new(EventJournal::new(
+                EventJournalConfig::default().capacity,
+            ))),
+            journal_config: EventJournalConfig::default(),
         }
     }
 
+    /// Overrides the default retransmission timeout, backoff and retry budget
+    pub fn with_retransmission_config(mut self, config: RetransmissionConfig) -> Self {
+        self.retransmission_config = config;
+        self
+    }
+
+    /// Enables striping the fragments of a session across several routes
+    pub fn with_multipath_config(mut self, config: MultipathConfig) -> Self {
+        self.multipath_config = config;
+        self
+    }
+
+    /// Overrides the default route cache invalidation threshold, or disables the cache
+    pub fn with_route_cache_config(mut self, config: RouteCacheConfig) -> Self {
+        self.route_cache_config = config;
+        self
+    }
+
+    /// Overrides the number of discovery rounds after which unseen nodes and
+    /// edges are removed, or disables their expiry
+    pub fn with_topology_aging_config(mut self, config: TopologyAgingConfig) -> Self {
+        self.topology_aging_config = config;
+        self
+    }
+
+    /// Overrides how long discovery floods must stay quiet to be considered converged
+    pub fn with_flood_tracker_config(mut self, config: FloodTrackerConfig) -> Self {
+        self.flood_tracker_config = config;
+        self
+    }
+
+    /// Overrides the default AIMD send window, or disables it
+    pub fn with_congestion_config(mut self, config: CongestionConfig) -> Self {
+        self.congestion_config = config;
+        self
+    }
+
+    /// Overrides the default deadline for reassembling incomplete sessions
+    pub fn with_reassembly_config(mut self, config: ReassemblyConfig) -> Self {
+        self.reassembly_config = config;
+        self
+    }
+
+    /// Overrides the default payload encoding of outgoing messages
+    pub fn with_framing_config(mut self, config: FramingConfig) -> Self {
+        self.framing_config = config;
+        self
+    }
+
+    /// Overrides the default binding of the UI servers, or disables the UI
+    pub fn with_ui_config(mut self, config: UiConfig) -> Self {
+        self.ui_config = config;
+        self
+    }
+
+    /// Enables the message history, or overrides its directory
+    pub fn with_history_config(mut self, config: HistoryConfig) -> Self {
+        self.history_config = config;
+        self
+    }
+
+    /// Overrides how long messages wait for a route to their destination,
+    /// or disables the outbox
+    pub fn with_outbox_config(mut self, config: OutboxConfig) -> Self {
+        self.outbox_config = config;
+        self
+    }
+
+    /// Overrides the number of events kept in the journal, or disables it
+    pub fn with_event_journal_config(mut self, config: EventJournalConfig) -> Self {
+        self.journal = Arc::new(Mutex::new(EventJournal::new(config.capacity)));
+        self.journal_config = config;
+        self
+    }
+
+    /// Returns the per-destination delivery metrics collected so far
+    pub fn metrics(&self) -> &ClientMetrics {
+        &self.metrics
+    }
+
     fn should_perform_discovery(&self) -> bool {
         self.last_discovery.elapsed() >= self.discovery_interval
     }
 
+    /// Runs the client until it receives a Stop command.
+    ///
+    /// Unless the UI endpoint was taken with `take_ui_endpoint`, it is handed
+    /// to the web UI, if enabled.
     pub fn run(&mut self) {
-        // Generate UI crossbeam channels
-        let (ui_to_ws_sender, ui_to_ws_receiver) = crossbeam_channel::unbounded();
-        let (ws_to_ui_sender, ws_to_ui_receiver) = crossbeam_channel::unbounded();
+        let ws_to_ui_sender = self.ui_events.clone();
+        let mut ui_to_ws_receiver = self.ui_requests.clone();
+        self.open_history();
 
-        self.run_ui(ui_to_ws_sender, ws_to_ui_receiver);
+        // Without a consumer the endpoint is dropped, so that messages for
+        // the UI do not pile up
+        let web_ui = match self.ui_endpoint.take() {
+            Some(endpoint) if self.ui_config.enabled => self.run_ui(endpoint),
+            _ => false,
+        };
 
         // Start network discovery
         info!("Client {} started network discovery", self.id);
         self.discover_network();
 
         let mut running = true;
+        let mut ui_disconnected = false;
         while running {
+            // Once every sender behind the UI endpoint is gone, the UI channel
+            // would be ready on every iteration
+            if ui_disconnected {
+                ui_to_ws_receiver = never();
+                ui_disconnected = false;
+            }
+
             // Check if we need to perform discovery
             if self.should_perform_discovery() {
                 info!("Client {}: Performing periodic network discovery", self.id);
-                self.discover_network();
+                self.start_discovery_round();
                 self.last_discovery = Instant::now();
             }
 
+            // Close the discovery floods that stopped bringing new edges
+            if !self.flood_tracker.outstanding.is_empty()
+                && !self.check_flood_convergence().is_empty()
+            {
+                // The discovery round may have found a route for queued messages
+                self.flush_outbox(&ws_to_ui_sender);
+            }
+            if !self.outbox.queues.is_empty() {
+                self.expire_outbox(&ws_to_ui_sender);
+            }
+
+            // Refresh the data served by the HTTP server
+            if self.last_snapshot_publish.elapsed() >= SNAPSHOT_PUBLISH_INTERVAL {
+                self.publish_ui_snapshots();
+            }
+
+            // Drop sessions whose missing fragments never arrived
+            if self.should_sweep_reassemblies() {
+                self.sweep_expired_reassemblies();
+            }
+
             select_biased! {
                 // Handle SC commands
                 recv(self.controller_recv) -> command => {
@@ -106,9 +380,13 @@ impl RustbustersClient {
                         match cmd {
                             HostCommand::Stop => {
                                 info!("Client {} - Received Stop command", self.id);
+                                self.report_command_result("Stop", Ok(()));
                                 running = false;
                             }
-                            _ => self.handle_command(cmd, &ws_to_ui_sender),
+                            _ => {
+                                // Failures are reported as command results
+                                let _ = self.handle_command(cmd, &ws_to_ui_sender);
+                            }
                         }
                     } else {
                         error!("Client {} - Error in receiving command", self.id);
@@ -117,12 +395,10 @@ impl RustbustersClient {
                 // Handle UI commands
                 recv(ui_to_ws_receiver) -> msg_to_srv => {
                     if let Ok(msg) = msg_to_srv {
-                        let server_id = msg.0;
-                        let message = msg.1;
-
-                        self.handle_ui_message(server_id, message, &ws_to_ui_sender);
+                        self.handle_ui_message(msg, &ws_to_ui_sender);
                     } else {
-                        error!("Client {} - Error in receiving command", self.id);
+                        info!("Client {}: UI endpoint dropped, ignoring the UI channel", self.id);
+                        ui_disconnected = true;
                     }
                 },
                 // Handle incoming packets
@@ -134,14 +410,16 @@ impl RustbustersClient {
                     }
                 },
                 default(Duration::from_millis(100)) => {
+                    self.check_retransmission_timers(&ws_to_ui_sender);
+                    self.release_all_queued_fragments();
                 }
             }
         }
 
         info!("Client {} shutting down", self.id);
         // Wait for threads to finish
-        if let Ok(mut clients) = CLIENTS_STATE.lock() {
-            clients.remove(&self.id);
+        if web_ui {
+            unregister_client(self.id);
         }
     }
 
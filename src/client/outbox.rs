@@ -0,0 +1,309 @@
+use crate::client::RustbustersClient;
+use crate::DeliveryUpdate;
+use common_utils::{HostMessage, ServerToClientMessage};
+use crossbeam_channel::Sender;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_QUEUED_PER_DESTINATION: usize = 64;
+/// Number of messages whose status is remembered for the UI
+const STATUS_CAPACITY: usize = 256;
+
+/// Parameters of the queue of messages waiting for a route to their destination
+#[derive(Debug, Clone, Copy)]
+pub struct OutboxConfig {
+    /// When disabled messages without a route fail immediately
+    pub enabled: bool,
+    /// Messages still without a route after this long fail
+    pub ttl: Duration,
+    /// Messages queued for a single destination, the oldest ones fail first
+    pub max_queued_per_destination: usize,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl: DEFAULT_TTL,
+            max_queued_per_destination: DEFAULT_MAX_QUEUED_PER_DESTINATION,
+        }
+    }
+}
+
+/// Delivery status of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageStatus {
+    /// Waiting in the outbox for a route to the destination
+    Queued,
+    /// Fragments sent, not all of them acknowledged yet
+    InFlight,
+    /// In flight, with some fragments moved to a different route
+    Rerouted,
+    /// Every fragment acknowledged
+    Delivered,
+    Failed,
+}
+
+/// A message waiting for a route
+#[derive(Debug, Clone)]
+pub(crate) struct OutboxEntry {
+    pub(crate) message_id: u64,
+    pub(crate) message: HostMessage,
+    pub(crate) queued_at: Instant,
+}
+
+/// Status of a message, as shown to the UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MessageStatusSnapshot {
+    pub message_id: u64,
+    pub destination: NodeId,
+    pub status: MessageStatus,
+}
+
+/// Queued messages and recent statuses, published for the UI
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OutboxSnapshot {
+    /// Number of queued messages, by destination
+    pub queued: BTreeMap<NodeId, usize>,
+    /// Most recent messages, oldest first
+    pub messages: Vec<MessageStatusSnapshot>,
+}
+
+/// Messages waiting for a route, by destination, and the status of the
+/// recently sent messages
+#[derive(Debug, Default)]
+pub(crate) struct Outbox {
+    pub(crate) queues: HashMap<NodeId, VecDeque<OutboxEntry>>,
+    // message_id -> (destination, status)
+    pub(crate) statuses: HashMap<u64, (NodeId, MessageStatus)>,
+    statuses_order: VecDeque<u64>,
+    // session_id -> message_id of the sessions in flight
+    pub(crate) sessions: HashMap<u64, u64>,
+}
+
+impl RustbustersClient {
+    /// Assigns the ID of a new message
+    pub(crate) fn next_message_id(&mut self) -> u64 {
+        self.message_ids.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Records the status of a message, and notifies the UI if it changed
+    ///
+    /// ### Arguments
+    /// * `message_id` - The ID of the message
+    /// * `destination` - The destination of the message
+    /// * `status` - The new status
+    pub(crate) fn set_message_status(
+        &mut self,
+        message_id: u64,
+        destination: NodeId,
+        status: MessageStatus,
+    ) {
+        let outbox = &mut self.outbox;
+        if !outbox.statuses.contains_key(&message_id) {
+            if outbox.statuses_order.len() >= STATUS_CAPACITY {
+                if let Some(oldest) = outbox.statuses_order.pop_front() {
+                    outbox.statuses.remove(&oldest);
+                }
+            }
+            outbox.statuses_order.push_back(message_id);
+        }
+        let previous = outbox.statuses.insert(message_id, (destination, status));
+
+        if previous.is_none_or(|(_, previous)| previous != status) {
+            // Nobody may be listening, e.g. with the UI disabled
+            let _ = self.delivery_updates.send(DeliveryUpdate {
+                message_id,
+                destination,
+                status,
+            });
+        }
+    }
+
+    /// Marks the message sent in a session as moved to a different route
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the session
+    /// * `destination` - The destination of the session
+    pub(crate) fn reroute_session_message(&mut self, session_id: u64, destination: NodeId) {
+        if let Some(message_id) = self.outbox.sessions.get(&session_id).copied() {
+            self.set_message_status(message_id, destination, MessageStatus::Rerouted);
+        }
+    }
+
+    /// Records the final status of the message sent in a session
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the completed or failed session
+    /// * `destination` - The destination of the session
+    /// * `status` - `Delivered` or `Failed`
+    pub(crate) fn complete_session_message(
+        &mut self,
+        session_id: u64,
+        destination: NodeId,
+        status: MessageStatus,
+    ) {
+        if let Some(message_id) = self.outbox.sessions.remove(&session_id) {
+            self.set_message_status(message_id, destination, status);
+        }
+    }
+
+    /// Queues a message until a route to its destination is found
+    ///
+    /// ### Arguments
+    /// * `message_id` - The ID of the message
+    /// * `destination_id` - The destination of the message
+    /// * `message` - The message
+    /// * `ws_to_ui_sender` - Channel sender for sending error messages back to the UI
+    pub(crate) fn enqueue_in_outbox(
+        &mut self,
+        message_id: u64,
+        destination_id: NodeId,
+        message: HostMessage,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        info!(
+            "Client {}: No route to {}, message {} queued in the outbox",
+            self.id, destination_id, message_id
+        );
+        let queue = self.outbox.queues.entry(destination_id).or_default();
+        queue.push_back(OutboxEntry {
+            message_id,
+            message,
+            queued_at: Instant::now(),
+        });
+
+        let overflow = queue
+            .len()
+            .saturating_sub(self.outbox_config.max_queued_per_destination);
+        let dropped: Vec<OutboxEntry> = queue.drain(..overflow).collect();
+        self.set_message_status(message_id, destination_id, MessageStatus::Queued);
+
+        for entry in dropped {
+            self.fail_outbox_entry(destination_id, entry, "Outbox full", ws_to_ui_sender);
+        }
+    }
+
+    /// Sends the queued messages whose destination became reachable
+    ///
+    /// ### Arguments
+    /// * `ws_to_ui_sender` - Channel sender for sending error messages back to the UI
+    pub(crate) fn flush_outbox(
+        &mut self,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        // Unreachable destinations wait for the periodic discovery round:
+        // rediscovering here would start a flood on every convergence
+        let mut destinations: Vec<NodeId> = self.outbox.queues.keys().copied().collect();
+        destinations.retain(|destination| self.has_path(*destination));
+
+        for destination in destinations {
+            let Some(queue) = self.outbox.queues.remove(&destination) else {
+                continue;
+            };
+            info!(
+                "Client {}: Route to {} found, sending {} queued messages",
+                self.id,
+                destination,
+                queue.len()
+            );
+            for entry in queue {
+                self.send_tracked_message(
+                    entry.message_id,
+                    destination,
+                    entry.message,
+                    ws_to_ui_sender,
+                );
+            }
+        }
+    }
+
+    /// Fails the queued messages older than the outbox TTL
+    ///
+    /// ### Arguments
+    /// * `ws_to_ui_sender` - Channel sender for sending error messages back to the UI
+    pub(crate) fn expire_outbox(
+        &mut self,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        let ttl = self.outbox_config.ttl;
+        let mut expired = Vec::new();
+        for (destination, queue) in &mut self.outbox.queues {
+            while queue
+                .front()
+                .is_some_and(|entry| entry.queued_at.elapsed() >= ttl)
+            {
+                if let Some(entry) = queue.pop_front() {
+                    expired.push((*destination, entry));
+                }
+            }
+        }
+        self.outbox.queues.retain(|_, queue| !queue.is_empty());
+
+        for (destination, entry) in expired {
+            self.fail_outbox_entry(
+                destination,
+                entry,
+                "Destination unreachable!",
+                ws_to_ui_sender,
+            );
+        }
+    }
+
+    fn fail_outbox_entry(
+        &mut self,
+        destination: NodeId,
+        entry: OutboxEntry,
+        reason: &str,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        warn!(
+            "Client {}: Queued message {} to {} failed: {}",
+            self.id, entry.message_id, destination, reason
+        );
+        self.set_message_status(entry.message_id, destination, MessageStatus::Failed);
+
+        if let HostMessage::FromClient(client_msg) = entry.message {
+            let error_msg = ServerToClientMessage::SendingError {
+                error: reason.to_string(),
+                message: client_msg,
+            };
+
+            // set to 0 because server_id is not relevant
+            if ws_to_ui_sender.send((0, error_msg)).is_err() {
+                warn!("Client {}: Unable to send error message to UI", self.id);
+            }
+        }
+    }
+
+    /// Builds the snapshot of the outbox published for the UI
+    pub(crate) fn outbox_snapshot(&self) -> OutboxSnapshot {
+        OutboxSnapshot {
+            queued: self
+                .outbox
+                .queues
+                .iter()
+                .map(|(destination, queue)| (*destination, queue.len()))
+                .collect(),
+            messages: self
+                .outbox
+                .statuses_order
+                .iter()
+                .filter_map(|message_id| {
+                    let (destination, status) = self.outbox.statuses.get(message_id)?;
+                    Some(MessageStatusSnapshot {
+                        message_id: *message_id,
+                        destination: *destination,
+                        status: *status,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
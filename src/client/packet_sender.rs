@@ -1,3 +1,4 @@
+use crate::client::outbox::MessageStatus;
 use crate::client::RustbustersClient;
 use common_utils::{HostEvent, HostMessage, PacketHeader, PacketTypeHeader, ServerToClientMessage};
 use crossbeam_channel::Sender;
@@ -18,84 +19,103 @@ impl RustbustersClient {
         destination_id: NodeId,
         message: HostMessage,
         ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        let message_id = self.next_message_id();
+        self.send_tracked_message(message_id, destination_id, message, ws_to_ui_sender);
+    }
+
+    /// Sends a message whose delivery status is tracked under the given ID.
+    /// Messages from the UI without a route wait in the outbox, if enabled.
+    ///
+    /// ### Arguments
+    /// * `message_id` - The ID of the message
+    /// * `destination_id` - The ID of the destination node
+    /// * `message` - The message to be sent
+    /// * `ws_to_ui_sender` - Channel sender for sending error messages back to the UI
+    pub(crate) fn send_tracked_message(
+        &mut self,
+        message_id: u64,
+        destination_id: NodeId,
+        message: HostMessage,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
     ) {
         // Compute the route to the destination
         if let Some(route) = self.find_weighted_path(destination_id) {
+            let encoding = self.payload_encoding_for(destination_id);
+            let fragments = match self.disassemble_message(&message, encoding) {
+                Ok(fragments) => fragments,
+                Err(err) => {
+                    warn!(
+                        "Client {}: Unable to encode message to {}: {}",
+                        self.id, destination_id, err
+                    );
+                    self.set_message_status(message_id, destination_id, MessageStatus::Failed);
+                    let HostMessage::FromClient(client_msg) = message else {
+                        return;
+                    };
+                    let error_msg = ServerToClientMessage::SendingError {
+                        error: format!("Unable to encode the message: {err}"),
+                        message: client_msg,
+                    };
+
+                    // set to 0 because server_id is not relevant
+                    if ws_to_ui_sender.send((0, error_msg)).is_err() {
+                        warn!("Client {}: Unable to send error message to UI", self.id);
+                    }
+                    return;
+                }
+            };
+
             // Increment session_id_counter
             self.session_id_counter += 1;
             let session_id = self.session_id_counter;
 
-            let fragments = self.disassemble_message(&message);
+            self.metrics.session_started(
+                session_id,
+                fragments
+                    .iter()
+                    .map(|fragment| u64::from(fragment.length))
+                    .sum(),
+            );
 
-            // Send the fragments along the route
-            for fragment in fragments {
-                debug!(
-                    "Client {}: Sending fragment {:?} of session {} to {}",
-                    self.id, fragment, session_id, destination_id
-                );
-                let fragment_index = fragment.fragment_index;
-                let packet = Packet {
-                    pack_type: PacketType::MsgFragment(fragment),
-                    routing_header: SourceRoutingHeader {
-                        hop_index: 1,
-                        hops: route.clone(),
-                    },
-                    session_id,
-                };
+            // Store the time the message was sent
+            self.pending_session_info.insert(
+                session_id,
+                (destination_id, message.clone(), Instant::now()),
+            );
+            self.outbox.sessions.insert(session_id, message_id);
+            self.set_message_status(message_id, destination_id, MessageStatus::InFlight);
 
-                // Store the time the message was sent
-                self.pending_session_info.insert(
+            let routes = self.stripe_routes(destination_id, route.clone(), fragments.len());
+            let packets = fragments
+                .into_iter()
+                .zip(routes)
+                .map(|(fragment, hops)| Packet {
+                    pack_type: PacketType::MsgFragment(fragment),
+                    routing_header: SourceRoutingHeader { hop_index: 1, hops },
                     session_id,
-                    (destination_id, message.clone(), Instant::now()),
-                );
-
-                // Send the packet to the first hop
-                let next_hop = packet.routing_header.hops[1];
-                if let Some(sender) = self.packet_send.get(&next_hop) {
-                    if let Err(e) = sender.send(packet.clone()) {
-                        warn!(
-                            "Client {}: Failed to send packet to {}: {:?}",
-                            self.id, next_hop, e
-                        );
-                        let error_msg = ServerToClientMessage::SendingError {
-                            error: "Failed to send message! Retry in a few seconds".to_string(),
-                            message: match message.clone() {
-                                HostMessage::FromClient(client_msg) => client_msg,
-                                _ => unreachable!("Client {}: Invalid message type", self.id),
-                            },
-                        };
+                })
+                .collect();
 
-                        // set to 0 because server_id is not relevant
-                        if ws_to_ui_sender.send((0, error_msg)).is_err() {
-                            warn!("Client {}: Unable to send error message to UI", self.id);
-                        }
-                    } else {
-                        self.send_to_sc(HostEvent::PacketSent(PacketHeader {
-                            session_id,
-                            pack_type: PacketTypeHeader::MsgFragment,
-                            routing_header: packet.routing_header.clone(),
-                        }));
-                        self.pending_sent
-                            .entry((session_id, fragment_index))
-                            .or_insert(packet);
-                        info!(
-                            "Client {}: Sent PacketSent event for session {} fragment {}",
-                            self.id, session_id, fragment_index
-                        );
-                    }
-                } else {
-                    warn!("Client {}: No sender for next hop {}", self.id, next_hop);
-                }
-            }
+            // Fragments are sent as the congestion window of the destination allows
+            self.enqueue_fragments(destination_id, packets);
 
             info!(
                 "Client {}: Sent message to {} via route {:?}",
                 self.id, destination_id, route
             );
+        } else if self.outbox_config.enabled && matches!(message, HostMessage::FromClient(_)) {
+            self.enqueue_in_outbox(message_id, destination_id, message, ws_to_ui_sender);
         } else {
             info!("Client {}: No route to {}", self.id, destination_id);
+            self.set_message_status(message_id, destination_id, MessageStatus::Failed);
+            let error = if self.is_discovery_converged() {
+                "Destination unreachable! Retry in a few seconds"
+            } else {
+                "Network discovery in progress! Retry in a few seconds"
+            };
             let error_msg = ServerToClientMessage::SendingError {
-                error: "Destination unreachable! Retry in a few seconds".to_string(),
+                error: error.to_string(),
                 message: match message {
                     HostMessage::FromClient(client_msg) => client_msg,
                     _ => return,
@@ -108,4 +128,66 @@ impl RustbustersClient {
             }
         }
     }
+
+    /// Sends a fragment to the first hop of its route and starts waiting for its Ack.
+    ///
+    /// A fragment that cannot be handed to the first hop stays pending as well:
+    /// its retransmission timer moves it to another route, or fails the session
+    /// once the retry budget is exhausted.
+    ///
+    /// ### Arguments
+    /// * `packet` - The fragment to be sent
+    pub(crate) fn transmit_fragment(&mut self, packet: Packet) {
+        let session_id = packet.session_id;
+        let PacketType::MsgFragment(fragment) = &packet.pack_type else {
+            return;
+        };
+        let fragment_index = fragment.fragment_index;
+        debug!(
+            "Client {}: Sending fragment {:?} of session {} to {:?}",
+            self.id,
+            fragment,
+            session_id,
+            packet.routing_header.hops.last()
+        );
+
+        // Send the packet to the first hop
+        let next_hop = packet.routing_header.hops[1];
+        match self.packet_send.get(&next_hop) {
+            Some(sender) => {
+                if let Err(e) = sender.send(packet.clone()) {
+                    warn!(
+                        "Client {}: Failed to send packet to {}: {:?}",
+                        self.id, next_hop, e
+                    );
+                } else {
+                    self.send_to_sc(HostEvent::PacketSent(PacketHeader {
+                        session_id,
+                        pack_type: PacketTypeHeader::MsgFragment,
+                        routing_header: packet.routing_header.clone(),
+                    }));
+                    if let Some(destination) = packet.routing_header.hops.last() {
+                        self.metrics.fragment_sent(*destination);
+                    }
+                    info!(
+                        "Client {}: Sent PacketSent event for session {} fragment {}",
+                        self.id, session_id, fragment_index
+                    );
+                }
+            }
+            None => warn!("Client {}: No sender for next hop {}", self.id, next_hop),
+        }
+
+        if !self
+            .pending_sent
+            .contains_key(&(session_id, fragment_index))
+        {
+            if let Some(destination) = packet.routing_header.hops.last() {
+                self.fragment_in_flight(*destination);
+            }
+            self.pending_sent
+                .insert((session_id, fragment_index), packet);
+        }
+        self.start_retransmission_timer(session_id, fragment_index);
+    }
 }
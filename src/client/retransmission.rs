@@ -0,0 +1,263 @@
+use crate::client::commands::CommandError;
+use crate::client::events::ClientEvent;
+use crate::client::outbox::MessageStatus;
+use crate::client::RustbustersClient;
+use common_utils::{HostMessage, ServerToClientMessage};
+use crossbeam_channel::Sender;
+use log::{error, info, warn};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+const DEFAULT_INITIAL_TIMEOUT: Duration = Duration::from_millis(1500);
+const DEFAULT_MAX_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_BACKOFF_FACTOR: f32 = 2.0;
+const DEFAULT_MAX_RETRIES: u32 = 5;
+
+/// Parameters of the per-fragment retransmission timer
+#[derive(Debug, Clone, Copy)]
+pub struct RetransmissionConfig {
+    /// Time to wait for an Ack (or Nack) before the first retransmission
+    pub initial_timeout: Duration,
+    /// Multiplier applied to the timeout after every retransmission
+    pub backoff_factor: f32,
+    /// Upper bound of the timeout after backoff
+    pub max_timeout: Duration,
+    /// Number of retransmissions after which the whole session is declared failed
+    pub max_retries: u32,
+}
+
+impl Default for RetransmissionConfig {
+    fn default() -> Self {
+        Self {
+            initial_timeout: DEFAULT_INITIAL_TIMEOUT,
+            backoff_factor: DEFAULT_BACKOFF_FACTOR,
+            max_timeout: DEFAULT_MAX_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+}
+
+/// Retransmission timer of a single in-flight fragment
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetransmissionTimer {
+    deadline: Instant,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl RetransmissionTimer {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+            timeout,
+            retries: 0,
+        }
+    }
+}
+
+impl RustbustersClient {
+    /// Starts (or restarts from scratch) the retransmission timer of a fragment.
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the message session
+    /// * `fragment_index` - The index of the fragment
+    pub(crate) fn start_retransmission_timer(&mut self, session_id: u64, fragment_index: u64) {
+        self.retransmission_timers.insert(
+            (session_id, fragment_index),
+            RetransmissionTimer::new(self.retransmission_config.initial_timeout),
+        );
+    }
+
+    /// Pushes the deadline of a fragment forward after it has been resent
+    /// for another reason (e.g. a Nack), without consuming the retry budget.
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the message session
+    /// * `fragment_index` - The index of the fragment
+    pub(crate) fn restart_retransmission_timer(&mut self, session_id: u64, fragment_index: u64) {
+        match self
+            .retransmission_timers
+            .get_mut(&(session_id, fragment_index))
+        {
+            Some(timer) => timer.deadline = Instant::now() + timer.timeout,
+            None => self.start_retransmission_timer(session_id, fragment_index),
+        }
+    }
+
+    /// Consumes a retry of a fragment resent because its receiver asked for it.
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the message session
+    /// * `fragment_index` - The index of the fragment
+    ///
+    /// ### Returns
+    /// false if the retry budget of the fragment is exhausted
+    pub(crate) fn charge_retransmission(&mut self, session_id: u64, fragment_index: u64) -> bool {
+        let initial_timeout = self.retransmission_config.initial_timeout;
+        let timer = self
+            .retransmission_timers
+            .entry((session_id, fragment_index))
+            .or_insert_with(|| RetransmissionTimer::new(initial_timeout));
+        if timer.retries >= self.retransmission_config.max_retries {
+            return false;
+        }
+        timer.retries += 1;
+        true
+    }
+
+    /// Resends every fragment whose Ack did not arrive in time.
+    ///
+    /// Each retransmission multiplies the timeout by the backoff factor.
+    /// When a fragment exhausts its retry budget the whole session is failed.
+    ///
+    /// ### Arguments
+    /// * `ws_to_ui_sender` - Channel sender for sending error messages back to the UI
+    pub(crate) fn check_retransmission_timers(
+        &mut self,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        let now = Instant::now();
+        let config = self.retransmission_config;
+
+        // Drop timers of fragments that are no longer pending
+        self.retransmission_timers
+            .retain(|key, _| self.pending_sent.contains_key(key));
+
+        let mut expired = Vec::new();
+        for key in self.pending_sent.keys() {
+            let timer = self
+                .retransmission_timers
+                .entry(*key)
+                .or_insert_with(|| RetransmissionTimer::new(config.initial_timeout));
+            if now >= timer.deadline {
+                expired.push(*key);
+            }
+        }
+
+        let mut failed_sessions = Vec::new();
+        let mut congested = Vec::new();
+        for (session_id, fragment_index) in expired {
+            if failed_sessions.contains(&session_id) {
+                continue;
+            }
+
+            let Some(timer) = self
+                .retransmission_timers
+                .get_mut(&(session_id, fragment_index))
+            else {
+                continue;
+            };
+
+            if timer.retries >= config.max_retries {
+                failed_sessions.push(session_id);
+                continue;
+            }
+
+            timer.retries += 1;
+            timer.timeout = timer
+                .timeout
+                .mul_f32(config.backoff_factor)
+                .min(config.max_timeout);
+            timer.deadline = now + timer.timeout;
+            let retries = timer.retries;
+
+            if let Some(mut packet) = self
+                .pending_sent
+                .get(&(session_id, fragment_index))
+                .cloned()
+            {
+                info!(
+                    "Client {}: Ack timeout for session {} fragment {}, retransmission {}/{}",
+                    self.id, session_id, fragment_index, retries, config.max_retries
+                );
+                // A missing Ack counts as a loss, once per destination and check
+                if let Some(destination) = packet.routing_header.hops.last().copied() {
+                    if !congested.contains(&destination) {
+                        self.on_fragment_dropped(destination);
+                        congested.push(destination);
+                    }
+                }
+                self.reroute_and_resend(&mut packet, fragment_index, true);
+                self.pending_sent
+                    .insert((session_id, fragment_index), packet);
+            }
+        }
+
+        for session_id in failed_sessions {
+            self.fail_session(
+                session_id,
+                "Message could not be delivered! Retry in a few seconds",
+                ws_to_ui_sender,
+            );
+        }
+    }
+
+    /// Gives up on a session, dropping all its pending fragments and
+    /// notifying the UI with a `SendingError`.
+    ///
+    /// ### Arguments
+    /// * `session_id` - The ID of the failed session
+    /// * `reason` - The error shown to the UI
+    /// * `ws_to_ui_sender` - Channel sender for sending error messages back to the UI
+    pub(crate) fn fail_session(
+        &mut self,
+        session_id: u64,
+        reason: &str,
+        ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
+    ) {
+        let route = self
+            .pending_sent
+            .iter()
+            .chain(&self.acked_fragments)
+            .find(|((key, _), _)| *key == session_id)
+            .map(|(_, packet)| packet.routing_header.hops.clone())
+            .unwrap_or_default();
+        let pending = self.pending_sent.len();
+        self.pending_sent.retain(|(key, _), _| *key != session_id);
+        if let Some(destination) = route.last() {
+            self.fragments_landed(*destination, pending - self.pending_sent.len());
+        }
+        self.retransmission_timers
+            .retain(|(key, _), _| *key != session_id);
+        self.acked_fragments
+            .retain(|(key, _), _| *key != session_id);
+        self.discard_queued_fragments(session_id);
+
+        let Some((destination, message, _)) = self.pending_session_info.remove(&session_id) else {
+            return;
+        };
+        self.metrics.session_failed(session_id, destination);
+        self.complete_session_message(session_id, destination, MessageStatus::Failed);
+
+        error!(
+            "Client {}: Session {} to {} failed: {}",
+            self.id, session_id, destination, reason
+        );
+        self.record_event(ClientEvent::SessionFailed {
+            session_id,
+            destination,
+            route,
+            reason: reason.to_string(),
+        });
+        self.report_command_result(
+            "Session",
+            Err(CommandError::SessionFailed {
+                session_id,
+                destination,
+                reason: reason.to_string(),
+            }),
+        );
+
+        if let HostMessage::FromClient(client_msg) = message {
+            let error_msg = ServerToClientMessage::SendingError {
+                error: reason.to_string(),
+                message: client_msg,
+            };
+
+            // set to 0 because server_id is not relevant
+            if ws_to_ui_sender.send((0, error_msg)).is_err() {
+                warn!("Client {}: Unable to send error message to UI", self.id);
+            }
+        }
+    }
+}
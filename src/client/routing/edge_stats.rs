@@ -1,5 +1,5 @@
+use crate::client::routing::EdgeStatsSnapshot;
 use crate::RustbustersClient;
-use petgraph::data::Build;
 use wg_2024::network::NodeId;
 
 /// Base weight for edges when no statistics are available
@@ -103,6 +103,21 @@ impl EdgeStats {
         BASE_WEIGHT * (1.0 + self.current_pdr * self.confidence + consecutive_penalty)
     }
 
+    /// Serializable copy of the statistics of the edge `from` -> `to`
+    pub(crate) fn snapshot(&self, from: NodeId, to: NodeId) -> EdgeStatsSnapshot {
+        EdgeStatsSnapshot {
+            from,
+            to,
+            packets_sent: self.packets_sent,
+            estimated_pdr: self.current_pdr,
+            confidence: self.confidence,
+            alpha: self.alpha,
+            consecutive_acks: self.consecutive_acks,
+            consecutive_nacks: self.consecutive_nacks,
+            weight: self.get_edge_weight(),
+        }
+    }
+
     pub(crate) fn get_estimated_pdr(&self) -> f32 {
         self.current_pdr
     }
@@ -148,7 +163,7 @@ impl RustbustersClient {
         let stats = self.get_or_create_edge_stats(dropped_from, dropped_to);
         stats.update(true);
         let weight = stats.get_edge_weight();
-        self.topology.update_edge(dropped_from, dropped_to, weight);
+        self.set_edge_weight(dropped_from, dropped_to, weight);
 
         self.register_successful_transmission(&nack_path[1..]);
     }
@@ -164,7 +179,7 @@ impl RustbustersClient {
 
             // Update weight in graph
             let weight = stats.get_edge_weight();
-            self.topology.update_edge(from, to, weight);
+            self.set_edge_weight(from, to, weight);
         }
     }
 }
@@ -0,0 +1,211 @@
+use crate::client::events::ClientEvent;
+use crate::RustbustersClient;
+use log::info;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use wg_2024::network::NodeId;
+
+const DEFAULT_QUIET_PERIOD: Duration = Duration::from_millis(500);
+const DEFAULT_ROUND_TIMEOUT: Duration = Duration::from_secs(5);
+/// Number of converged rounds kept for inspection
+const COMPLETED_ROUNDS_CAPACITY: usize = 16;
+/// Number of (initiator, flood_id) pairs remembered for incoming requests
+const SEEN_REQUESTS_CAPACITY: usize = 1024;
+
+/// Parameters of the detection of converged discovery rounds
+#[derive(Debug, Clone, Copy)]
+pub struct FloodTrackerConfig {
+    /// A round is converged once its responses stopped changing the topology for this long
+    pub quiet_period: Duration,
+    /// A round with no responses at all is closed after this long
+    pub round_timeout: Duration,
+}
+
+impl Default for FloodTrackerConfig {
+    fn default() -> Self {
+        Self {
+            quiet_period: DEFAULT_QUIET_PERIOD,
+            round_timeout: DEFAULT_ROUND_TIMEOUT,
+        }
+    }
+}
+
+/// Bookkeeping of a flood started by this client
+#[derive(Debug, Clone)]
+pub(crate) struct FloodRound {
+    pub(crate) flood_id: u64,
+    pub(crate) started_at: Instant,
+    /// Responses received so far
+    pub(crate) responses: u32,
+    /// Responses that carried no edge not already seen in this round
+    pub(crate) duplicates: u32,
+    /// Last time a response brought a new edge
+    last_change_at: Instant,
+    /// Edges (min, max) already reported by a response of this round
+    seen_edges: HashSet<(NodeId, NodeId)>,
+    /// Time the round converged at, if it did
+    pub(crate) converged_at: Option<Instant>,
+}
+
+/// Outstanding and recently converged floods of this client, and the floods
+/// of other initiators it answered
+#[derive(Debug, Default)]
+pub(crate) struct FloodTracker {
+    pub(crate) outstanding: HashMap<u64, FloodRound>,
+    pub(crate) completed: VecDeque<FloodRound>,
+    // (initiator, flood_id) -> number of requests received
+    seen_requests: HashMap<(NodeId, u64), u32>,
+    seen_requests_order: VecDeque<(NodeId, u64)>,
+}
+
+impl RustbustersClient {
+    /// Registers a flood just started by this client.
+    ///
+    /// ### Arguments
+    /// * `flood_id` - The ID of the new flood
+    pub(crate) fn track_flood(&mut self, flood_id: u64) {
+        let now = Instant::now();
+        self.flood_tracker.outstanding.insert(
+            flood_id,
+            FloodRound {
+                flood_id,
+                started_at: now,
+                responses: 0,
+                duplicates: 0,
+                last_change_at: now,
+                seen_edges: HashSet::new(),
+                converged_at: None,
+            },
+        );
+        self.publish_discovery_state();
+    }
+
+    /// Records a response to one of this client's floods and filters out the
+    /// edges it already learned in the same round.
+    ///
+    /// ### Arguments
+    /// * `flood_id` - The ID of the flood
+    /// * `edges` - The (min, max) edges of the response path trace
+    ///
+    /// ### Returns
+    /// The edges that still need to be processed: all of them for untracked floods
+    pub(crate) fn record_flood_response(
+        &mut self,
+        flood_id: u64,
+        edges: Vec<(NodeId, NodeId)>,
+    ) -> Vec<(NodeId, NodeId)> {
+        let Some(round) = self.flood_tracker.outstanding.get_mut(&flood_id) else {
+            return edges;
+        };
+
+        round.responses += 1;
+        let new_edges: Vec<_> = edges
+            .into_iter()
+            .filter(|edge| round.seen_edges.insert(*edge))
+            .collect();
+
+        if new_edges.is_empty() {
+            round.duplicates += 1;
+        } else {
+            round.last_change_at = Instant::now();
+        }
+        new_edges
+    }
+
+    /// Records a flood request from another initiator.
+    ///
+    /// ### Arguments
+    /// * `initiator_id` - The initiator of the flood
+    /// * `flood_id` - The ID of the flood
+    ///
+    /// ### Returns
+    /// How many times this (initiator, flood_id) pair was received, this one included
+    pub(crate) fn record_flood_request(&mut self, initiator_id: NodeId, flood_id: u64) -> u32 {
+        let tracker = &mut self.flood_tracker;
+        let key = (initiator_id, flood_id);
+
+        if !tracker.seen_requests.contains_key(&key) {
+            if tracker.seen_requests_order.len() >= SEEN_REQUESTS_CAPACITY {
+                if let Some(oldest) = tracker.seen_requests_order.pop_front() {
+                    tracker.seen_requests.remove(&oldest);
+                }
+            }
+            tracker.seen_requests_order.push_back(key);
+        }
+
+        let count = tracker.seen_requests.entry(key).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Closes the outstanding floods whose responses stopped bringing new edges
+    /// for the quiet period, or that got no response before the round timeout.
+    ///
+    /// ### Returns
+    /// The IDs of the floods that converged with this check
+    pub(crate) fn check_flood_convergence(&mut self) -> Vec<u64> {
+        let config = self.flood_tracker_config;
+        let now = Instant::now();
+
+        let converged: Vec<u64> = self
+            .flood_tracker
+            .outstanding
+            .values()
+            .filter(|round| {
+                if round.responses == 0 {
+                    now.duration_since(round.started_at) >= config.round_timeout
+                } else {
+                    now.duration_since(round.last_change_at) >= config.quiet_period
+                }
+            })
+            .map(|round| round.flood_id)
+            .collect();
+
+        for flood_id in &converged {
+            let Some(mut round) = self.flood_tracker.outstanding.remove(flood_id) else {
+                continue;
+            };
+            round.converged_at = Some(now);
+            round.seen_edges.clear();
+            info!(
+                "Client {}: Discovery flood {} converged after {:?} with {} responses ({} duplicates)",
+                self.id,
+                round.flood_id,
+                now.duration_since(round.started_at),
+                round.responses,
+                round.duplicates
+            );
+
+            self.record_event(ClientEvent::DiscoveryConverged {
+                flood_id: round.flood_id,
+                responses: round.responses,
+                duplicates: round.duplicates,
+            });
+
+            if self.flood_tracker.completed.len() >= COMPLETED_ROUNDS_CAPACITY {
+                self.flood_tracker.completed.pop_front();
+            }
+            self.flood_tracker.completed.push_back(round);
+        }
+        if !converged.is_empty() {
+            self.publish_discovery_state();
+        }
+
+        converged
+    }
+
+    /// Whether network discovery has settled: at least one flood converged
+    /// and none is still outstanding.
+    ///
+    /// Once the client runs on its own thread, the same state is read with
+    /// `EventJournalReader::is_discovery_converged`.
+    pub fn is_discovery_converged(&self) -> bool {
+        self.flood_tracker.outstanding.is_empty() && !self.flood_tracker.completed.is_empty()
+    }
+
+    /// Shares whether discovery has settled with the readers of the journal
+    fn publish_discovery_state(&self) {
+        let converged = self.is_discovery_converged();
+        self.journal.lock().unwrap().discovery_converged = converged;
+    }
+}
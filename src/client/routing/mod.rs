@@ -1,3 +1,17 @@
 pub(crate) mod edge_stats;
+mod flood_tracker;
+mod multipath;
 mod networ_discovery;
 mod path_finding;
+mod route_cache;
+mod topology_aging;
+mod topology_snapshot;
+
+pub(crate) use flood_tracker::FloodTracker;
+pub use flood_tracker::FloodTrackerConfig;
+pub use multipath::MultipathConfig;
+pub(crate) use route_cache::RouteCache;
+pub use route_cache::{RouteCacheConfig, RouteCacheStats};
+pub use topology_aging::TopologyAgingConfig;
+pub(crate) use topology_aging::{edge_key, TopologyAges};
+pub use topology_snapshot::{EdgeSnapshot, EdgeStatsSnapshot, NodeSnapshot, TopologySnapshot};
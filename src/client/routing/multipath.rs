@@ -0,0 +1,84 @@
+use crate::RustbustersClient;
+use log::info;
+use wg_2024::network::NodeId;
+
+const DEFAULT_MAX_PATHS: usize = 3;
+
+/// Parameters of the striping of a session's fragments across several routes
+#[derive(Debug, Clone, Copy)]
+pub struct MultipathConfig {
+    /// When disabled every fragment of a session follows the best route
+    pub enabled: bool,
+    /// Maximum number of routes a session is striped across
+    pub max_paths: usize,
+}
+
+impl Default for MultipathConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_paths: DEFAULT_MAX_PATHS,
+        }
+    }
+}
+
+impl RustbustersClient {
+    /// Chooses the route of every fragment of a new session.
+    ///
+    /// With multipath enabled, fragments are spread over the k best routes
+    /// in proportion to the inverse of their weight, using a smooth weighted
+    /// round-robin so that consecutive fragments alternate between routes.
+    ///
+    /// # Arguments
+    /// * `destination` - Destination node ID
+    /// * `best_route` - The route returned by `find_weighted_path`
+    /// * `n_fragments` - Number of fragments of the session
+    ///
+    /// # Returns
+    /// One route per fragment, in fragment order
+    pub(crate) fn stripe_routes(
+        &self,
+        destination: NodeId,
+        best_route: Vec<NodeId>,
+        n_fragments: usize,
+    ) -> Vec<Vec<NodeId>> {
+        if !self.multipath_config.enabled || n_fragments < 2 {
+            return vec![best_route; n_fragments];
+        }
+
+        let routes = self.find_k_shortest_paths(destination, self.multipath_config.max_paths);
+        if routes.len() < 2 {
+            return vec![best_route; n_fragments];
+        }
+
+        // Better routes (lower weight) get a larger share of the fragments
+        let shares: Vec<f32> = routes
+            .iter()
+            .map(|route| 1.0 / self.path_cost(route).max(f32::EPSILON))
+            .collect();
+        let total: f32 = shares.iter().sum();
+
+        let mut current = vec![0.0; routes.len()];
+        let mut counts = vec![0; routes.len()];
+        let mut assigned = Vec::with_capacity(n_fragments);
+        for _ in 0..n_fragments {
+            for (weight, share) in current.iter_mut().zip(&shares) {
+                *weight += share;
+            }
+            let chosen = current
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map_or(0, |(index, _)| index);
+            current[chosen] -= total;
+            counts[chosen] += 1;
+            assigned.push(routes[chosen].clone());
+        }
+
+        info!(
+            "Client {}: Striping {} fragments to {} across routes {:?} ({:?} fragments each)",
+            self.id, n_fragments, destination, routes, counts
+        );
+        assigned
+    }
+}
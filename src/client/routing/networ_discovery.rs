@@ -19,6 +19,8 @@ impl RustbustersClient {
         // Generate a unique flood_id
         self.flood_id_counter += 1;
         let flood_id = self.flood_id_counter;
+        self.track_flood(flood_id);
+        self.metrics.runtime.discovery_rounds += 1;
 
         // Initialize the FloodRequest
         let flood_request = FloodRequest {
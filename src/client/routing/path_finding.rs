@@ -1,6 +1,6 @@
 use crate::RustbustersClient;
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use wg_2024::network::NodeId;
 use wg_2024::packet::NodeType;
 
@@ -38,18 +38,160 @@ impl RustbustersClient {
     /// - Client can only connect to Drones
     /// - Drones can connect to other Drones or Servers
     /// - Server must be the final destination
+    ///
+    /// The result is served from the route cache while the topology is unchanged.
     pub(crate) fn find_weighted_path(&mut self, dst: NodeId) -> Option<Vec<NodeId>> {
+        if let Some(path) = self.cached_route(dst) {
+            return Some(path);
+        }
+
+        let node_types = self.known_nodes.lock().unwrap().clone();
+        match self.shortest_path(self.id, dst, &node_types, &HashSet::new(), &HashSet::new()) {
+            Some(path) => {
+                self.cache_route(dst, &path);
+                Some(path)
+            }
+            None => {
+                self.discover_network();
+                None
+            }
+        }
+    }
+
+    /// Tells whether a valid path to a destination is known, without touching
+    /// the route cache or starting a discovery.
+    ///
+    /// # Arguments
+    /// * `dst` - Destination node ID
+    pub(crate) fn has_path(&self, dst: NodeId) -> bool {
+        let node_types = self.known_nodes.lock().unwrap().clone();
+        self.shortest_path(self.id, dst, &node_types, &HashSet::new(), &HashSet::new())
+            .is_some()
+    }
+
+    /// Finds up to `k` loopless paths to a destination, ordered by total weight,
+    /// using Yen's algorithm.
+    ///
+    /// # Arguments
+    /// * `dst` - Destination node ID
+    /// * `k` - Maximum number of paths to return
+    ///
+    /// # Returns
+    /// The paths found, the first one being the one returned by `find_weighted_path`.
+    /// Empty if the destination is unreachable.
+    pub(crate) fn find_k_shortest_paths(&self, dst: NodeId, k: usize) -> Vec<Vec<NodeId>> {
+        let mut paths: Vec<Vec<NodeId>> = Vec::new();
+        if k == 0 {
+            return paths;
+        }
+        // Snapshot the node types once instead of locking for every edge
+        let node_types = self.known_nodes.lock().unwrap().clone();
+        let Some(first) =
+            self.shortest_path(self.id, dst, &node_types, &HashSet::new(), &HashSet::new())
+        else {
+            return paths;
+        };
+        paths.push(first);
+
+        let mut candidates: Vec<(f32, Vec<NodeId>)> = Vec::new();
+        while paths.len() < k {
+            let last = paths[paths.len() - 1].clone();
+
+            // Deviate from the last path at every node but the destination
+            for i in 0..last.len() - 1 {
+                let spur_node = last[i];
+                let root = &last[..=i];
+
+                // Edges leaving the root that are already used by a known path
+                let excluded_edges: HashSet<(NodeId, NodeId)> = paths
+                    .iter()
+                    .filter(|path| path.len() > i + 1 && path[..=i] == *root)
+                    .map(|path| (path[i], path[i + 1]))
+                    .collect();
+                // The root nodes cannot be crossed again
+                let excluded_nodes: HashSet<NodeId> = root[..i].iter().copied().collect();
+
+                if let Some(spur_path) = self.shortest_path(
+                    spur_node,
+                    dst,
+                    &node_types,
+                    &excluded_nodes,
+                    &excluded_edges,
+                ) {
+                    let mut candidate = root[..i].to_vec();
+                    candidate.extend(spur_path);
+
+                    if !paths.contains(&candidate)
+                        && !candidates.iter().any(|(_, path)| *path == candidate)
+                    {
+                        candidates.push((self.path_cost(&candidate), candidate));
+                    }
+                }
+            }
+
+            // The cheapest candidate becomes the next path
+            let Some(best) = candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, (cost_a, path_a)), (_, (cost_b, path_b))| {
+                    cost_a
+                        .partial_cmp(cost_b)
+                        .unwrap_or(Ordering::Equal)
+                        .then(path_a.len().cmp(&path_b.len()))
+                })
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            paths.push(candidates.swap_remove(best).1);
+        }
+
+        paths
+    }
+
+    /// Total weight of a path in the current topology
+    ///
+    /// # Arguments
+    /// * `path` - The nodes of the path, in order
+    pub(crate) fn path_cost(&self, path: &[NodeId]) -> f32 {
+        path.windows(2)
+            .map(|hop| {
+                *self
+                    .topology
+                    .edge_weight(hop[0], hop[1])
+                    .unwrap_or(&f32::INFINITY)
+            })
+            .sum()
+    }
+
+    /// Dijkstra's algorithm from `src` to `dst`, avoiding the given nodes and
+    /// directed edges. Follows the same path validity rules as `find_weighted_path`.
+    ///
+    /// # Arguments
+    /// * `src` - Node the path starts from
+    /// * `dst` - Destination node ID
+    /// * `node_types` - Snapshot of the known nodes and their types
+    /// * `excluded_nodes` - Nodes the path cannot cross
+    /// * `excluded_edges` - Edges (from, to) the path cannot follow
+    fn shortest_path(
+        &self,
+        src: NodeId,
+        dst: NodeId,
+        node_types: &HashMap<NodeId, NodeType>,
+        excluded_nodes: &HashSet<NodeId>,
+        excluded_edges: &HashSet<(NodeId, NodeId)>,
+    ) -> Option<Vec<NodeId>> {
         let mut distance: HashMap<NodeId, f32> = HashMap::new();
         let mut heap: BinaryHeap<(FloatKey, NodeId)> = BinaryHeap::new();
         let mut prev: HashMap<NodeId, NodeId> = HashMap::new();
         // Initialize distances
-        distance.insert(self.id, 0.0);
-        heap.push((FloatKey(0.0), self.id));
+        distance.insert(src, 0.0);
+        heap.push((FloatKey(0.0), src));
 
         while let Some((FloatKey(cost), node)) = heap.pop() {
             // If we reached the destination, and it's a server, build the path
             if node == dst {
-                return if let Some(NodeType::Server) = self.get_node_type(node) {
+                return if let Some(NodeType::Server) = node_types.get(&node) {
                     let mut path = Vec::new();
                     let mut current = Some(node);
 
@@ -74,6 +216,11 @@ impl RustbustersClient {
 
             // Explore neighbors
             for neighbor in self.topology.neighbors(node) {
+                if excluded_nodes.contains(&neighbor) || excluded_edges.contains(&(node, neighbor))
+                {
+                    continue;
+                }
+
                 let edge_weight = *self
                     .topology
                     .edge_weight(node, neighbor)
@@ -81,7 +228,7 @@ impl RustbustersClient {
                 let next_cost = cost + edge_weight;
 
                 // Path validity rules
-                match (self.get_node_type(node), self.get_node_type(neighbor)) {
+                match (node_types.get(&node), node_types.get(&neighbor)) {
                     (Some(NodeType::Client), Some(NodeType::Drone))
                     | (Some(NodeType::Drone), Some(NodeType::Drone | NodeType::Server)) => {
                         if next_cost < *distance.get(&neighbor).unwrap_or(&f32::INFINITY) {
@@ -95,12 +242,6 @@ impl RustbustersClient {
             }
         }
 
-        self.discover_network();
         None
     }
-
-    /// Helper function to get the type of a node from known_nodes
-    fn get_node_type(&self, node_id: NodeId) -> Option<NodeType> {
-        self.known_nodes.lock().unwrap().get(&node_id).copied()
-    }
 }
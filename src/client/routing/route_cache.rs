@@ -0,0 +1,167 @@
+use crate::RustbustersClient;
+use log::debug;
+use petgraph::data::Build;
+use serde::Serialize;
+use std::collections::HashMap;
+use wg_2024::network::NodeId;
+
+const DEFAULT_WEIGHT_DRIFT_THRESHOLD: f32 = 0.25;
+
+/// Parameters of the per-destination route cache
+#[derive(Debug, Clone, Copy)]
+pub struct RouteCacheConfig {
+    /// When disabled every lookup runs a fresh Dijkstra
+    pub enabled: bool,
+    /// Relative change of an edge weight, since the last invalidation,
+    /// after which all cached routes are dropped
+    pub weight_drift_threshold: f32,
+}
+
+impl Default for RouteCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weight_drift_threshold: DEFAULT_WEIGHT_DRIFT_THRESHOLD,
+        }
+    }
+}
+
+/// Counters of the route cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct RouteCacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that ran Dijkstra
+    pub misses: u64,
+    /// Number of times the whole cache was dropped
+    pub invalidations: u64,
+}
+
+/// Best route to a destination, with its weight when it was computed
+#[derive(Debug, Clone)]
+struct CachedRoute {
+    path: Vec<NodeId>,
+    cost: f32,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RouteCache {
+    routes: HashMap<NodeId, CachedRoute>,
+    // (from, to) -> weight of the edge when the cache was last invalidated
+    baseline_weights: HashMap<(NodeId, NodeId), f32>,
+    stats: RouteCacheStats,
+}
+
+impl RouteCache {
+    fn clear(&mut self) {
+        self.routes.clear();
+        self.baseline_weights.clear();
+        self.stats.invalidations += 1;
+    }
+}
+
+impl RustbustersClient {
+    /// Returns the cached route to a destination, counting the hit or miss.
+    ///
+    /// # Arguments
+    /// * `dst` - Destination node ID
+    pub(crate) fn cached_route(&mut self, dst: NodeId) -> Option<Vec<NodeId>> {
+        if !self.route_cache_config.enabled {
+            return None;
+        }
+
+        match self.route_cache.routes.get(&dst) {
+            Some(route) => {
+                self.route_cache.stats.hits += 1;
+                debug!(
+                    "Client {}: Route cache hit for {}: {:?} (cost {})",
+                    self.id, dst, route.path, route.cost
+                );
+                Some(route.path.clone())
+            }
+            None => {
+                self.route_cache.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Drops the cached route to a single destination, so that the next
+    /// lookup runs Dijkstra on the current weights.
+    ///
+    /// # Arguments
+    /// * `dst` - Destination node ID
+    pub(crate) fn forget_cached_route(&mut self, dst: NodeId) {
+        self.route_cache.routes.remove(&dst);
+    }
+
+    /// Stores the route just computed for a destination.
+    ///
+    /// # Arguments
+    /// * `dst` - Destination node ID
+    /// * `path` - The best route to the destination
+    pub(crate) fn cache_route(&mut self, dst: NodeId, path: &[NodeId]) {
+        if !self.route_cache_config.enabled {
+            return;
+        }
+
+        let cost = self.path_cost(path);
+        self.route_cache.routes.insert(
+            dst,
+            CachedRoute {
+                path: path.to_vec(),
+                cost,
+            },
+        );
+    }
+
+    /// Drops every cached route, after a change of the topology graph.
+    ///
+    /// # Arguments
+    /// * `reason` - What changed, for the logs
+    pub(crate) fn invalidate_route_cache(&mut self, reason: &str) {
+        if self.route_cache.routes.is_empty() {
+            // Nothing cached: only restart measuring drift from the current weights
+            self.route_cache.baseline_weights.clear();
+            return;
+        }
+
+        debug!(
+            "Client {}: Invalidating route cache ({}), stats {:?}",
+            self.id, reason, self.route_cache.stats
+        );
+        self.route_cache.clear();
+    }
+
+    /// Updates the weight of an edge, invalidating the route cache when the
+    /// weight drifted too far from its value at the last invalidation.
+    ///
+    /// # Arguments
+    /// * `from` - Source node ID
+    /// * `to` - Destination node ID
+    /// * `weight` - New weight of the edge
+    pub(crate) fn set_edge_weight(&mut self, from: NodeId, to: NodeId, weight: f32) {
+        let previous = self.topology.edge_weight(from, to).copied();
+        self.topology.update_edge(from, to, weight);
+
+        let Some(previous) = previous else {
+            self.invalidate_route_cache("new edge");
+            return;
+        };
+
+        let baseline = *self
+            .route_cache
+            .baseline_weights
+            .entry((from, to))
+            .or_insert(previous);
+        let drift = (weight - baseline).abs() / baseline.max(f32::EPSILON);
+        if drift > self.route_cache_config.weight_drift_threshold {
+            self.invalidate_route_cache("edge weight drift");
+        }
+    }
+
+    /// Returns the hit, miss and invalidation counters of the route cache
+    pub fn route_cache_stats(&self) -> RouteCacheStats {
+        self.route_cache.stats
+    }
+}
@@ -0,0 +1,157 @@
+use crate::RustbustersClient;
+use log::info;
+use std::collections::HashMap;
+use std::time::Instant;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+const DEFAULT_MAX_MISSED_ROUNDS: u64 = 3;
+
+/// Parameters of the expiry of nodes and edges that stopped showing up in floods
+#[derive(Debug, Clone, Copy)]
+pub struct TopologyAgingConfig {
+    /// When disabled nodes and edges are only removed by Nacks and commands
+    pub enabled: bool,
+    /// Number of consecutive discovery rounds a node or edge may be missing
+    /// from every flood response before it is removed
+    pub max_missed_rounds: u64,
+}
+
+impl Default for TopologyAgingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_missed_rounds: DEFAULT_MAX_MISSED_ROUNDS,
+        }
+    }
+}
+
+/// Discovery round and time at which a node or edge was last seen in a flood
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LastSeen {
+    pub(crate) round: u64,
+    pub(crate) at: Instant,
+}
+
+/// Last-seen records of the nodes and edges of the topology
+#[derive(Debug, Default)]
+pub(crate) struct TopologyAges {
+    /// Periodic discovery rounds started so far
+    pub(crate) round: u64,
+    pub(crate) nodes: HashMap<NodeId, LastSeen>,
+    // Edges are undirected: the key is (min, max)
+    pub(crate) edges: HashMap<(NodeId, NodeId), LastSeen>,
+}
+
+/// Key of an undirected edge
+pub(crate) fn edge_key(a: NodeId, b: NodeId) -> (NodeId, NodeId) {
+    (a.min(b), a.max(b))
+}
+
+impl RustbustersClient {
+    /// Marks the nodes and edges of a flood path trace as seen in the current round.
+    ///
+    /// ### Arguments
+    /// * `path_trace` - The nodes crossed by the flood, in order
+    pub(crate) fn refresh_topology_ages(&mut self, path_trace: &[(NodeId, NodeType)]) {
+        let seen = LastSeen {
+            round: self.topology_ages.round,
+            at: Instant::now(),
+        };
+
+        for (node_id, _) in path_trace {
+            self.topology_ages.nodes.insert(*node_id, seen);
+        }
+        for window in path_trace.windows(2) {
+            self.topology_ages
+                .edges
+                .insert(edge_key(window[0].0, window[1].0), seen);
+        }
+    }
+
+    /// Expires stale nodes and edges, then starts a new periodic discovery round.
+    pub(crate) fn start_discovery_round(&mut self) {
+        if self.topology_aging_config.enabled {
+            self.expire_stale_topology();
+        }
+        self.topology_ages.round += 1;
+        self.discover_network();
+    }
+
+    /// Removes the nodes and edges missing from the floods of the last
+    /// `max_missed_rounds` discovery rounds.
+    ///
+    /// Nodes and edges that were never seen in a flood start aging now.
+    ///
+    /// ### Returns
+    /// The number of nodes and edges removed
+    pub(crate) fn expire_stale_topology(&mut self) -> usize {
+        let round = self.topology_ages.round;
+        let max_missed = self.topology_aging_config.max_missed_rounds;
+        let now = LastSeen {
+            round,
+            at: Instant::now(),
+        };
+        let is_stale = |seen: &LastSeen| round.saturating_sub(seen.round) >= max_missed;
+
+        let mut stale_nodes = Vec::new();
+        for node in self.topology.nodes() {
+            if node == self.id {
+                continue;
+            }
+            let seen = self.topology_ages.nodes.entry(node).or_insert(now);
+            if is_stale(seen) {
+                stale_nodes.push((node, seen.at));
+            }
+        }
+
+        let mut stale_edges = Vec::new();
+        for (a, b, _) in self.topology.all_edges() {
+            let seen = self
+                .topology_ages
+                .edges
+                .entry(edge_key(a, b))
+                .or_insert(now);
+            if is_stale(seen) {
+                stale_edges.push((a, b, seen.at));
+            }
+        }
+
+        for (a, b, last_seen) in &stale_edges {
+            info!(
+                "Client {}: Edge {}-{} not seen for {:?}, removing it",
+                self.id,
+                a,
+                b,
+                last_seen.elapsed()
+            );
+            self.topology.remove_edge(*a, *b);
+            self.topology_ages.edges.remove(&edge_key(*a, *b));
+            self.edge_stats.remove(&(*a, *b));
+            self.edge_stats.remove(&(*b, *a));
+        }
+
+        for (node, last_seen) in &stale_nodes {
+            info!(
+                "Client {}: Node {} not seen for {:?}, removing it",
+                self.id,
+                node,
+                last_seen.elapsed()
+            );
+            self.topology.remove_node(*node);
+            self.topology_ages.nodes.remove(node);
+            self.topology_ages
+                .edges
+                .retain(|(a, b), _| a != node && b != node);
+            self.edge_stats
+                .retain(|(from, to), _| from != node && to != node);
+            self.known_nodes.lock().unwrap().remove(node);
+        }
+
+        let removed = stale_nodes.len() + stale_edges.len();
+        if removed > 0 {
+            self.topology_changed("stale nodes or edges expired");
+        }
+        removed
+    }
+}
@@ -0,0 +1,133 @@
+use crate::RustbustersClient;
+use serde::Serialize;
+use std::fmt::Write;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+/// A node of the topology with its type
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeSnapshot {
+    pub id: NodeId,
+    /// `None` if the node appears in the graph but its type is unknown
+    pub node_type: Option<NodeType>,
+}
+
+/// An undirected edge of the topology with its current routing weight
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EdgeSnapshot {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub weight: f32,
+}
+
+/// Statistics of a directed link, as used to compute edge weights
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EdgeStatsSnapshot {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub packets_sent: u64,
+    /// Packet drop rate estimated with an exponential moving average
+    pub estimated_pdr: f32,
+    pub confidence: f32,
+    /// Current learning rate of the moving average
+    pub alpha: f32,
+    pub consecutive_acks: u32,
+    pub consecutive_nacks: u32,
+    /// Routing weight derived from the statistics above
+    pub weight: f32,
+}
+
+/// Point-in-time copy of a client's view of the network
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TopologySnapshot {
+    pub client_id: NodeId,
+    pub nodes: Vec<NodeSnapshot>,
+    pub edges: Vec<EdgeSnapshot>,
+    pub edge_stats: Vec<EdgeStatsSnapshot>,
+}
+
+impl TopologySnapshot {
+    /// Renders the topology as a Graphviz DOT graph.
+    ///
+    /// Nodes are shaped by type and edges are labelled with their weight
+    /// and, when known, the estimated PDR of both directions.
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("graph client_{} {{\n", self.client_id);
+
+        for node in &self.nodes {
+            let shape = match node.node_type {
+                Some(NodeType::Client) => "box",
+                Some(NodeType::Server) => "doubleoctagon",
+                Some(NodeType::Drone) => "ellipse",
+                None => "plaintext",
+            };
+            let label = match node.node_type {
+                Some(node_type) => format!("{node_type:?} {}", node.id),
+                None => node.id.to_string(),
+            };
+            let _ = writeln!(dot, "    {} [label=\"{label}\", shape={shape}];", node.id);
+        }
+
+        for edge in &self.edges {
+            let mut label = format!("{:.2}", edge.weight);
+            for stats in self.edge_stats.iter().filter(|stats| {
+                (stats.from, stats.to) == (edge.from, edge.to)
+                    || (stats.from, stats.to) == (edge.to, edge.from)
+            }) {
+                let _ = write!(
+                    label,
+                    "\\n{}->{} pdr {:.2}",
+                    stats.from, stats.to, stats.estimated_pdr
+                );
+            }
+            let _ = writeln!(dot, "    {} -- {} [label=\"{label}\"];", edge.from, edge.to);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl RustbustersClient {
+    /// Copies the current topology, node types and edge statistics.
+    ///
+    /// Nodes and edges are sorted by ID so that snapshots can be compared.
+    pub fn topology_snapshot(&self) -> TopologySnapshot {
+        let known_nodes = self.known_nodes.lock().unwrap().clone();
+
+        let mut nodes: Vec<NodeSnapshot> = self
+            .topology
+            .nodes()
+            .map(|id| NodeSnapshot {
+                id,
+                node_type: known_nodes.get(&id).copied(),
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.id);
+
+        let mut edges: Vec<EdgeSnapshot> = self
+            .topology
+            .all_edges()
+            .map(|(a, b, weight)| EdgeSnapshot {
+                from: a.min(b),
+                to: a.max(b),
+                weight: *weight,
+            })
+            .collect();
+        edges.sort_by_key(|edge| (edge.from, edge.to));
+
+        let mut edge_stats: Vec<EdgeStatsSnapshot> = self
+            .edge_stats
+            .iter()
+            .map(|((from, to), stats)| stats.snapshot(*from, *to))
+            .collect();
+        edge_stats.sort_by_key(|stats| (stats.from, stats.to));
+
+        TopologySnapshot {
+            client_id: self.id,
+            nodes,
+            edges,
+            edge_stats,
+        }
+    }
+}
@@ -1,23 +1,127 @@
+use crate::client::outbox::MessageStatus;
 use crate::RustbustersClient;
 use common_utils::{ClientToServerMessage, HostMessage, ServerToClientMessage};
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 use wg_2024::network::NodeId;
 
+/// A message from the UI for a server
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    /// ID the delivery status of the message is reported under
+    pub message_id: u64,
+    pub server_id: NodeId,
+    pub message: ClientToServerMessage,
+}
+
+/// Hands messages from the UI to the client, assigning each one an ID
+#[derive(Debug, Clone)]
+pub struct MessageSender {
+    sender: Sender<OutgoingMessage>,
+    message_ids: Arc<AtomicU64>,
+}
+
+impl MessageSender {
+    pub(crate) fn new(sender: Sender<OutgoingMessage>, message_ids: Arc<AtomicU64>) -> Self {
+        Self {
+            sender,
+            message_ids,
+        }
+    }
+
+    /// Hands a message to the client, to be sent to a server
+    ///
+    /// ### Arguments
+    /// * `server_id` - The ID of the destination server
+    /// * `message` - The message to be sent to the server
+    ///
+    /// Returns the ID of the message, or None if the client is not running anymore
+    pub fn send(&self, server_id: NodeId, message: ClientToServerMessage) -> Option<u64> {
+        let message_id = self.message_ids.fetch_add(1, Ordering::Relaxed) + 1;
+        self.sender
+            .send(OutgoingMessage {
+                message_id,
+                server_id,
+                message,
+            })
+            .ok()?;
+        Some(message_id)
+    }
+}
+
+/// Change of the delivery status of a message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeliveryUpdate {
+    pub message_id: u64,
+    pub destination: NodeId,
+    pub status: MessageStatus,
+}
+
+/// The UI side of a client: the web UI is one consumer of it, an embedding
+/// application or a test can take it instead to drive the client directly
+#[derive(Debug)]
+pub struct UiEndpoint {
+    /// Messages to send to a server
+    pub to_client: MessageSender,
+    /// Messages received from the servers, as (server_id, message)
+    pub from_client: Receiver<(NodeId, ServerToClientMessage)>,
+    /// Delivery status changes of the messages sent
+    pub delivery_updates: Receiver<DeliveryUpdate>,
+}
+
 impl RustbustersClient {
+    /// Takes the UI side of the client, so that `run` does not start the web UI.
+    ///
+    /// Returns None if it was already taken.
+    pub fn take_ui_endpoint(&mut self) -> Option<UiEndpoint> {
+        self.ui_endpoint.take()
+    }
+
     /// Handles messages from the UI by forwarding them to the appropriate server
     /// 
     /// ### Arguments
-    /// * `server_id` - The ID of the destination server
-    /// * `message` - The message to be sent to the server
+    /// * `outgoing` - The message to be sent, with its ID and destination server
     /// * `ws_to_ui_sender` - Channel sender for sending responses back to the UI
     pub(crate) fn handle_ui_message(
         &mut self,
-        server_id: NodeId,
-        message: ClientToServerMessage,
+        outgoing: OutgoingMessage,
         ws_to_ui_sender: &Sender<(NodeId, ServerToClientMessage)>,
     ) {
+        let OutgoingMessage {
+            message_id,
+            server_id,
+            message,
+        } = outgoing;
+        self.record_sent_message(server_id, &message);
         let message_to_send = HostMessage::FromClient(message);
+        self.metrics.runtime.ui_messages_forwarded += 1;
+
+        self.send_tracked_message(message_id, server_id, message_to_send, ws_to_ui_sender);
+    }
+
+    /// Refreshes the snapshots of the client state read by the HTTP server
+    pub(crate) fn publish_ui_snapshots(&mut self) {
+        self.last_snapshot_publish = Instant::now();
 
-        self.send_message(server_id, message_to_send, ws_to_ui_sender);
+        let topology = self.topology_snapshot();
+        if let Ok(mut shared) = self.shared_topology.lock() {
+            *shared = topology;
+        }
+        let route_cache = self.route_cache_stats();
+        let runtime = &mut self.metrics.runtime;
+        runtime.pending_sent = self.pending_sent.len();
+        runtime.pending_received = self.pending_received.len();
+        runtime.known_nodes = self.known_nodes.lock().map_or(0, |nodes| nodes.len());
+        runtime.route_cache = route_cache;
+        if let Ok(mut shared) = self.shared_metrics.lock() {
+            shared.clone_from(&self.metrics);
+        }
+        let outbox = self.outbox_snapshot();
+        if let Ok(mut shared) = self.shared_outbox.lock() {
+            *shared = outbox;
+        }
     }
 }
@@ -1,7 +1,16 @@
 pub(crate) mod client;
 mod ui;
 
-pub use client::RustbustersClient;
+pub use client::{
+    ClientEvent, ClientMetrics, CommandError, CommandResult, CongestionConfig, DeliveryUpdate,
+    DestinationMetrics, EdgeSnapshot, EdgeStatsSnapshot, EventJournalConfig, EventJournalReader,
+    FloodTrackerConfig, FramingConfig, HistoryConfig, HistoryEntry, HistoryMessage, JournalEntry,
+    LatencyHistogram, MessageSender, MessageStatus, MessageStatusSnapshot, MultipathConfig,
+    NackCounts, NodeSnapshot, OutboxConfig, OutboxSnapshot, OutgoingMessage, PacketCounts,
+    PayloadCodec, ReassemblyConfig, RetransmissionConfig, RouteCacheConfig, RouteCacheStats,
+    RuntimeMetrics, RustbustersClient, TopologyAgingConfig, TopologySnapshot, UiEndpoint,
+};
+pub use ui::{UiAddresses, UiConfig};
 
 #[cfg(test)]
 mod tests;
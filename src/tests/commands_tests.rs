@@ -1,6 +1,8 @@
 use crate::client::routing::edge_stats::BASE_WEIGHT;
 
 use super::create_test_client;
+use crate::client::commands::COMMAND_RESULTS_CAPACITY;
+use crate::CommandError;
 use common_utils::HostCommand;
 use crossbeam_channel::unbounded;
 use wg_2024::packet::{NodeType, PacketType};
@@ -34,11 +36,20 @@ fn test_send_random_message() {
         .lock()
         .unwrap()
         .insert(3, NodeType::Server);
+    // the recipient of the random message
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(4, NodeType::Client);
 
     // setup packet senders
     client.packet_send.insert(2, packet_2_tx.clone());
 
-    client.handle_command(HostCommand::SendRandomMessage(dest), &tx);
+    assert_eq!(
+        client.handle_command(HostCommand::SendRandomMessage(dest), &tx),
+        Ok(())
+    );
 
     if let Ok(packet) = packet_2_rx.try_recv() {
         assert_eq!(packet.routing_header.hops, vec![1, 2, 3]);
@@ -62,7 +73,10 @@ fn test_discover_network() {
         .insert(2, NodeType::Drone);
     client.packet_send.insert(2, packet_2_tx);
 
-    client.handle_command(HostCommand::DiscoverNetwork, &tx);
+    assert_eq!(
+        client.handle_command(HostCommand::DiscoverNetwork, &tx),
+        Ok(())
+    );
 
     // Verify that discovery packets are sent to neighbors
     if let Ok(packet) = packet_2_rx.try_recv() {
@@ -93,7 +107,10 @@ fn test_add_remove_sender() {
         .insert(sender_id, NodeType::Drone);
 
     // Test Add Sender
-    client.handle_command(HostCommand::AddSender(sender_id, sender.clone()), &tx);
+    assert_eq!(
+        client.handle_command(HostCommand::AddSender(sender_id, sender.clone()), &tx),
+        Ok(())
+    );
     assert!(client.packet_send.contains_key(&sender_id));
 
     // Verify that discovery packet was sent to the new sender
@@ -118,7 +135,10 @@ fn test_add_remove_sender() {
         .unwrap()
         .insert(2, NodeType::Drone);
 
-    client.handle_command(HostCommand::RemoveSender(sender_id), &tx);
+    assert_eq!(
+        client.handle_command(HostCommand::RemoveSender(sender_id), &tx),
+        Ok(())
+    );
     assert!(!client.packet_send.contains_key(&sender_id));
 
     // Verify that discovery packet was sent to remaining nodes
@@ -133,3 +153,61 @@ fn test_add_remove_sender() {
         panic!("No discovery packet was sent after remove");
     }
 }
+
+#[test]
+fn test_send_random_message_without_known_clients() {
+    let (mut client, _, _, _) = create_test_client();
+    let (tx, _) = unbounded();
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(3, NodeType::Server);
+
+    assert_eq!(
+        client.handle_command(HostCommand::SendRandomMessage(3), &tx),
+        Err(CommandError::NoKnownClient)
+    );
+    assert!(client.pending_sent.is_empty());
+}
+
+#[test]
+fn test_command_results_are_reported() {
+    let (mut client, _, _, _) = create_test_client();
+    let results = client
+        .take_command_results()
+        .expect("Results already taken");
+    assert!(client.take_command_results().is_none());
+    let (tx, _) = unbounded();
+
+    let _ = client.handle_command(HostCommand::DiscoverNetwork, &tx);
+    let _ = client.handle_command(HostCommand::RemoveSender(7), &tx);
+
+    let discover = results.try_recv().unwrap();
+    assert_eq!(discover.client_id, 1);
+    assert_eq!(discover.seq, 1);
+    assert_eq!(discover.command, "DiscoverNetwork");
+    assert_eq!(discover.outcome, Ok(()));
+
+    let remove = results.try_recv().unwrap();
+    assert_eq!(remove.seq, 2);
+    assert_eq!(remove.command, "RemoveSender");
+    assert_eq!(remove.outcome, Err(CommandError::UnknownSender(7)));
+}
+
+#[test]
+fn test_untaken_command_results_keep_the_most_recent() {
+    let (mut client, _, _, _) = create_test_client();
+    let (tx, _) = unbounded();
+
+    for _ in 0..COMMAND_RESULTS_CAPACITY + 10 {
+        let _ = client.handle_command(HostCommand::DiscoverNetwork, &tx);
+    }
+
+    let results = client
+        .take_command_results()
+        .expect("Results already taken");
+    let seqs: Vec<u64> = results.try_iter().map(|result| result.seq).collect();
+    assert_eq!(seqs.len(), COMMAND_RESULTS_CAPACITY);
+    assert_eq!(seqs[0], 11);
+}
@@ -0,0 +1,215 @@
+use crate::client::routing::edge_stats::BASE_WEIGHT;
+use crate::tests::create_test_client;
+use crate::{CongestionConfig, RetransmissionConfig, RustbustersClient};
+use common_utils::{ClientToServerMessage, HostMessage};
+use crossbeam_channel::{unbounded, Receiver};
+use std::time::Duration;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{NackType, NodeType, Packet};
+
+/// Client (1) -> Drone (2) -> Server (3)
+fn setup_route(client: &mut RustbustersClient) -> Receiver<Packet> {
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+    packet_2_rx
+}
+
+fn large_message() -> HostMessage {
+    HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "A".repeat(1000),
+    })
+}
+
+#[test]
+fn test_window_limits_fragments_in_flight() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_congestion_config(CongestionConfig {
+        initial_window: 2.0,
+        ..Default::default()
+    });
+    let packet_2_rx = setup_route(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+
+    let sent: Vec<Packet> = packet_2_rx.try_iter().collect();
+    assert_eq!(sent.len(), 2);
+    let queued = client.congestion_windows[&3].queue.len();
+    assert!(queued > 0);
+
+    // Every Ack grows the window and releases queued fragments
+    client.handle_ack(sent[0].session_id, 0);
+    client.release_all_queued_fragments();
+
+    assert_eq!(packet_2_rx.len(), 1);
+    assert!(client.congestion_windows[&3].size > 2.0);
+    assert_eq!(client.congestion_windows[&3].queue.len(), queued - 1);
+}
+
+#[test]
+fn test_drop_shrinks_window() {
+    let (mut client, _, _, _) = create_test_client();
+    let packet_2_rx = setup_route(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+
+    let nack_header = SourceRoutingHeader {
+        hop_index: 1,
+        hops: vec![2, 1],
+    };
+    client.handle_nack(
+        packet.session_id,
+        0,
+        NackType::Dropped,
+        &nack_header,
+        &ui_tx,
+    );
+
+    let window = &client.congestion_windows[&3];
+    assert_eq!(
+        window.size,
+        CongestionConfig::default().initial_window / 2.0
+    );
+    assert_eq!(window.decreases, 1);
+}
+
+#[test]
+fn test_ack_timeout_shrinks_window() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_retransmission_config(RetransmissionConfig {
+        initial_timeout: Duration::ZERO,
+        max_timeout: Duration::ZERO,
+        ..Default::default()
+    });
+    let packet_2_rx = setup_route(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+    packet_2_rx.try_iter().for_each(drop);
+
+    // Every fragment in flight timed out, the window shrinks only once
+    client.check_retransmission_timers(&ui_tx);
+
+    let window = &client.congestion_windows[&3];
+    assert_eq!(
+        window.size,
+        CongestionConfig::default().initial_window / 2.0
+    );
+    assert_eq!(window.decreases, 1);
+}
+
+#[test]
+fn test_fragments_in_flight_are_counted_per_destination() {
+    let (mut client, _, _, _) = create_test_client();
+    let packet_2_rx = setup_route(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+    let initial_window = CongestionConfig::default().initial_window as usize;
+    assert_eq!(client.congestion_windows[&3].in_flight, initial_window);
+
+    client.handle_ack(packet.session_id, 0);
+    assert_eq!(client.congestion_windows[&3].in_flight, initial_window - 1);
+    client.release_all_queued_fragments();
+    assert_eq!(client.congestion_windows[&3].in_flight, initial_window);
+
+    client.fail_session(packet.session_id, "test", &ui_tx);
+    assert_eq!(client.congestion_windows[&3].in_flight, 0);
+}
+
+#[test]
+fn test_disabled_window_sends_burst() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_congestion_config(CongestionConfig {
+        enabled: false,
+        ..Default::default()
+    });
+    let packet_2_rx = setup_route(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+
+    assert!(packet_2_rx.len() > CongestionConfig::default().initial_window as usize);
+    assert!(client.congestion_windows[&3].queue.is_empty());
+}
+
+#[test]
+fn test_session_is_not_delivered_while_fragments_are_queued() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_congestion_config(CongestionConfig {
+        initial_window: 1.0,
+        ..Default::default()
+    });
+    let packet_2_rx = setup_route(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+    assert!(!client.congestion_windows[&3].queue.is_empty());
+
+    // The only fragment in flight is acked, the others are still queued
+    client.handle_ack(packet.session_id, 0);
+
+    assert!(client.pending_session_info.contains_key(&packet.session_id));
+    assert_eq!(client.metrics().destinations[&3].messages_delivered, 0);
+}
+
+#[test]
+fn test_fragment_without_first_hop_stays_pending() {
+    let (mut client, _, _, _) = create_test_client();
+    let _packet_2_rx = setup_route(&mut client);
+    client.packet_send.clear();
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+
+    // Nothing was sent, but the fragments wait for their retransmission
+    // timer instead of being lost
+    assert!(!client.pending_sent.is_empty());
+    assert!(client
+        .pending_sent
+        .keys()
+        .all(|key| client.retransmission_timers.contains_key(key)));
+}
+
+#[test]
+fn test_resend_requested_by_receiver_is_not_a_drop() {
+    let (mut client, _, _, _) = create_test_client();
+    let packet_2_rx = setup_route(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.send_message(3, large_message(), &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+    client.handle_ack(packet.session_id, 0);
+    packet_2_rx.try_iter().for_each(drop);
+
+    // The server found the session corrupted and asks for the fragment again
+    let nack_header = SourceRoutingHeader {
+        hop_index: 2,
+        hops: vec![3, 2, 1],
+    };
+    client.handle_nack(
+        packet.session_id,
+        0,
+        NackType::Dropped,
+        &nack_header,
+        &ui_tx,
+    );
+
+    assert!(packet_2_rx.try_recv().is_ok());
+    let window = &client.congestion_windows[&3];
+    assert_eq!(window.decreases, 0);
+    assert!(window.size > CongestionConfig::default().initial_window);
+    assert!(!client.edge_stats.contains_key(&(3, 2)));
+}
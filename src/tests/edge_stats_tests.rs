@@ -1,5 +1,6 @@
 use crate::tests::create_test_client;
 use common_utils::{ClientToServerMessage, HostMessage};
+use crossbeam_channel::unbounded;
 use wg_2024::network::SourceRoutingHeader;
 use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
 
@@ -65,6 +66,7 @@ fn test_edge_stats_ack_handling() {
 #[test]
 fn test_edge_stats_nack_handling() {
     let (mut client, _, _, _) = create_test_client();
+    let (ui_tx, _) = unbounded();
 
     // Setup path where drop occurred
     let path = vec![3, 2, 1]; // NACK path is reversed
@@ -85,7 +87,13 @@ fn test_edge_stats_nack_handling() {
     };
 
     // Handle NACK
-    client.handle_nack(session_id, fragment_index, NackType::Dropped, &nack_header);
+    client.handle_nack(
+        session_id,
+        fragment_index,
+        NackType::Dropped,
+        &nack_header,
+        &ui_tx,
+    );
 
     // Verify edge stats for the dropping edge
     let stats = client.get_or_create_edge_stats(path[0], path[1]);
@@ -96,6 +104,7 @@ fn test_edge_stats_nack_handling() {
 #[test]
 fn test_edge_stats_multiple_nacks() {
     let (mut client, _, _, _) = create_test_client();
+    let (ui_tx, _) = unbounded();
 
     let path = vec![3, 2, 1];
     let session_id = 1;
@@ -115,7 +124,13 @@ fn test_edge_stats_multiple_nacks() {
 
     // Send multiple NACKs
     for _ in 0..3 {
-        client.handle_nack(session_id, fragment_index, NackType::Dropped, &nack_header);
+        client.handle_nack(
+            session_id,
+            fragment_index,
+            NackType::Dropped,
+            &nack_header,
+            &ui_tx,
+        );
     }
 
     // Verify edge stats show deteriorating condition
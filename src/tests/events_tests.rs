@@ -0,0 +1,162 @@
+use crate::client::routing::edge_stats::BASE_WEIGHT;
+use crate::tests::create_test_client;
+use crate::{ClientEvent, CommandError, EventJournalConfig, ReassemblyConfig};
+use common_utils::{ClientToServerMessage, HostEvent, HostMessage, PacketHeader, PacketTypeHeader};
+use crossbeam_channel::unbounded;
+use std::time::Duration;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, NodeType, FRAGMENT_DSIZE};
+
+#[test]
+fn test_delivered_message_is_journaled_and_reported() {
+    let (mut client, event_rx, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+    client.handle_ack(packet.session_id, 0);
+
+    let entries = client.event_journal().entries(None, 10);
+    assert!(matches!(
+        entries.last().unwrap().event,
+        ClientEvent::MessageDelivered { destination: 3, session_id, .. } if session_id == packet.session_id
+    ));
+    // Delivered messages keep reaching the SC as HostMessageSent
+    assert!(event_rx
+        .try_iter()
+        .any(|event| matches!(event, HostEvent::HostMessageSent(3, ..))));
+}
+
+#[test]
+fn test_failed_session_is_reported_to_the_sc() {
+    let (mut client, event_rx, _, _) = create_test_client();
+    let results = client
+        .take_command_results()
+        .expect("Results already taken");
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+    client.fail_session(packet.session_id, "test", &ui_tx);
+
+    let entries = client.event_journal().entries(None, 10);
+    assert!(matches!(
+        &entries.last().unwrap().event,
+        ClientEvent::SessionFailed { destination: 3, route, .. } if *route == vec![1, 2, 3]
+    ));
+    // The SC reads the failure from the command results, no packet is made up
+    let result = results.try_recv().expect("No result reported");
+    assert_eq!(result.command, "Session");
+    assert!(matches!(
+        result.outcome,
+        Err(CommandError::SessionFailed { session_id, destination: 3, .. })
+            if session_id == packet.session_id
+    ));
+    assert!(!event_rx.try_iter().any(|event| matches!(
+        event,
+        HostEvent::PacketSent(PacketHeader {
+            pack_type: PacketTypeHeader::Nack,
+            ..
+        })
+    )));
+}
+
+#[test]
+fn test_journal_is_read_after_a_sequence_number() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_event_journal_config(EventJournalConfig {
+        capacity: 2,
+        ..EventJournalConfig::default()
+    });
+
+    for _ in 0..3 {
+        client.topology_changed("test");
+    }
+
+    // Only the two most recent events are kept
+    let journal = client.event_journal();
+    let entries = journal.entries(None, 10);
+    assert_eq!(
+        entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(journal.entries(Some(1), 10).len(), 1);
+    assert!(journal.entries(Some(2), 10).is_empty());
+
+    let json = serde_json::to_value(&entries[1]).unwrap();
+    assert_eq!(json["kind"], "topology_changed");
+    assert_eq!(json["seq"], 2);
+}
+
+#[test]
+fn test_expired_reassembly_is_journaled() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_reassembly_config(ReassemblyConfig {
+        deadline: Duration::ZERO,
+        sweep_interval: Duration::ZERO,
+        ..Default::default()
+    });
+    let (ui_tx, _) = unbounded();
+    let fragment = Fragment {
+        fragment_index: 0,
+        total_n_fragments: 2,
+        length: FRAGMENT_DSIZE as u8,
+        data: [b'a'; FRAGMENT_DSIZE],
+    };
+    let route = SourceRoutingHeader {
+        hop_index: 2,
+        hops: vec![3, 2, 1],
+    };
+
+    client.handle_message_fragment(&fragment, 42, &route, &ui_tx);
+    client.sweep_expired_reassemblies();
+
+    let entries = client.event_journal().entries(None, 10);
+    assert!(matches!(
+        entries.last().map(|entry| &entry.event),
+        Some(ClientEvent::ReassemblyExpired {
+            session_id: 42,
+            received: 1,
+            total: 2
+        })
+    ));
+}
+
+#[test]
+fn test_disabled_journal_records_nothing() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_event_journal_config(EventJournalConfig {
+        enabled: false,
+        ..EventJournalConfig::default()
+    });
+
+    client.topology_changed("test");
+
+    assert!(client.event_journal().entries(None, 10).is_empty());
+}
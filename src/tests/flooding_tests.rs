@@ -0,0 +1,85 @@
+use crate::tests::create_test_client;
+use crate::{ClientEvent, FloodTrackerConfig};
+use crossbeam_channel::unbounded;
+use std::time::Duration;
+use wg_2024::packet::{FloodRequest, FloodResponse, NodeType, PacketType};
+
+fn server_flood_response(flood_id: u64) -> FloodResponse {
+    FloodResponse {
+        flood_id,
+        path_trace: vec![
+            (1, NodeType::Client),
+            (2, NodeType::Drone),
+            (3, NodeType::Server),
+        ],
+    }
+}
+
+#[test]
+fn test_duplicate_flood_responses_are_counted() {
+    let (mut client, _, _, _) = create_test_client();
+    client.discover_network();
+    let flood_id = *client.flood_tracker.outstanding.keys().next().unwrap();
+
+    client.handle_flood_response(&server_flood_response(flood_id));
+    client.handle_flood_response(&server_flood_response(flood_id));
+
+    let round = &client.flood_tracker.outstanding[&flood_id];
+    assert_eq!(round.responses, 2);
+    assert_eq!(round.duplicates, 1);
+    assert!(client.topology.contains_edge(2, 3));
+}
+
+#[test]
+fn test_discovery_round_converges() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_flood_tracker_config(FloodTrackerConfig {
+        quiet_period: Duration::ZERO,
+        round_timeout: Duration::from_secs(60),
+    });
+    client.discover_network();
+    let flood_id = *client.flood_tracker.outstanding.keys().next().unwrap();
+
+    // Without responses the round is still waiting for the network
+    let journal = client.event_journal();
+    assert!(client.check_flood_convergence().is_empty());
+    assert!(!client.is_discovery_converged());
+    assert!(!journal.is_discovery_converged());
+
+    client.handle_flood_response(&server_flood_response(flood_id));
+
+    assert_eq!(client.check_flood_convergence(), vec![flood_id]);
+    assert!(client.is_discovery_converged());
+    // Readers of the journal on another thread see it too
+    assert!(journal.is_discovery_converged());
+    assert_eq!(client.flood_tracker.completed[0].responses, 1);
+
+    let entries = client.event_journal().entries(None, 10);
+    assert!(matches!(
+        entries.last().map(|entry| &entry.event),
+        Some(ClientEvent::DiscoveryConverged { flood_id: id, responses: 1, .. }) if *id == flood_id
+    ));
+}
+
+#[test]
+fn test_repeated_flood_requests_are_answered_once() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    let flood_request = FloodRequest {
+        flood_id: 9,
+        initiator_id: 5,
+        path_trace: vec![(5, NodeType::Client), (2, NodeType::Drone)],
+    };
+    client.handle_flood_request(&flood_request, 0);
+    client.handle_flood_request(&flood_request, 0);
+
+    assert_eq!(client.record_flood_request(5, 9), 3);
+    let responses: Vec<_> = packet_2_rx.try_iter().collect();
+    assert_eq!(responses.len(), 1);
+    assert!(matches!(
+        responses[0].pack_type,
+        PacketType::FloodResponse(ref response) if response.flood_id == 9
+    ));
+}
@@ -1,15 +1,24 @@
+use crate::client::fragmentation::{fragment_encoding, PayloadEncoding, ReassemblyError};
+use crate::client::routing::edge_stats::BASE_WEIGHT;
 use crate::tests::create_test_client;
-use common_utils::{ClientToServerMessage, HostMessage, MessageBody, MessageContent};
-use wg_2024::packet::FRAGMENT_DSIZE;
+use crate::{FramingConfig, PayloadCodec};
+use common_utils::{
+    ClientToServerMessage, HostMessage, MessageBody, MessageContent, ServerToClientMessage,
+};
+use crossbeam_channel::unbounded;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{NodeType, PacketType, FRAGMENT_DSIZE};
 
 #[test]
 fn test_small_message_fragmentation() {
-    let (client, _, _, _) = create_test_client();
+    let (mut client, _, _, _) = create_test_client();
     let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
         name: "Alice".to_string(),
     });
 
-    let fragments = client.disassemble_message(&message);
+    let fragments = client
+        .disassemble_message(&message, PayloadEncoding::Legacy)
+        .unwrap();
     assert_eq!(fragments.len(), 1);
     assert_eq!(fragments[0].fragment_index, 0);
     assert_eq!(fragments[0].total_n_fragments, 1);
@@ -19,7 +28,7 @@ fn test_small_message_fragmentation() {
 
 #[test]
 fn test_large_message_fragmentation() {
-    let (client, _, _, _) = create_test_client();
+    let (mut client, _, _, _) = create_test_client();
     let large_content = "A".repeat(FRAGMENT_DSIZE * 3);
     let message = HostMessage::FromClient(ClientToServerMessage::SendPrivateMessage {
         recipient_id: 2,
@@ -30,7 +39,9 @@ fn test_large_message_fragmentation() {
         },
     });
 
-    let fragments = client.disassemble_message(&message);
+    let fragments = client
+        .disassemble_message(&message, PayloadEncoding::Legacy)
+        .unwrap();
     assert!(fragments.len() > 1);
 
     // Check fragment properties
@@ -50,7 +61,9 @@ fn test_fragment_reassembly() {
 	});
 
     // Fragment the message
-    let fragments = client.disassemble_message(&original_message);
+    let fragments = client
+        .disassemble_message(&original_message, PayloadEncoding::Legacy)
+        .unwrap();
     let session_id = 1234;
 
     // Simulate receiving fragments
@@ -83,7 +96,9 @@ fn test_incomplete_reassembly() {
 	});
 
     // Fragment the message
-    let fragments = client.disassemble_message(&message);
+    let fragments = client
+        .disassemble_message(&message, PayloadEncoding::Legacy)
+        .unwrap();
     let session_id = 5678;
 
     // Simulate receiving fragments but skip one
@@ -98,4 +113,286 @@ fn test_incomplete_reassembly() {
     // Attempt reassembly should fail
     let result = client.reassemble_fragments(session_id);
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_framed_cbor_payload_with_zero_bytes() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_framing_config(FramingConfig {
+        compression: false,
+        ..FramingConfig::default()
+    });
+    let original_message = HostMessage::FromClient(ClientToServerMessage::SendPrivateMessage {
+        recipient_id: 0,
+        message: MessageBody {
+            sender_id: 0,
+            content: MessageContent::Text("\0".repeat(FRAGMENT_DSIZE * 2)),
+            timestamp: "12:00".to_string(),
+        },
+    });
+
+    let fragments = client
+        .disassemble_message(
+            &original_message,
+            PayloadEncoding::Framed(PayloadCodec::Cbor),
+        )
+        .unwrap();
+    assert!(fragments.len() > 2);
+    assert!(fragments[0].data[..fragments[0].length as usize].contains(&0));
+
+    let session_id = 4321;
+    client.pending_received.insert(
+        session_id,
+        (
+            fragments.iter().map(|f| Some(f.clone())).collect(),
+            fragments.len() as u64,
+        ),
+    );
+
+    match client.reassemble_fragments(session_id) {
+        Ok(HostMessage::FromClient(ClientToServerMessage::SendPrivateMessage {
+            recipient_id,
+            message,
+        })) => {
+            assert_eq!(recipient_id, 0);
+            assert!(matches!(
+                message.content,
+                MessageContent::Text(text) if text == "\0".repeat(FRAGMENT_DSIZE * 2)
+            ));
+        }
+        _ => panic!("Unexpected reassembly result"),
+    }
+}
+
+#[test]
+fn test_framed_payload_length_mismatch() {
+    let (mut client, _, _, _) = create_test_client();
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+
+    let mut fragments = client
+        .disassemble_message(&message, PayloadEncoding::Framed(PayloadCodec::Json))
+        .unwrap();
+    // Lose the last byte of the payload
+    fragments[0].length -= 1;
+
+    let session_id = 8765;
+    client
+        .pending_received
+        .insert(session_id, (vec![Some(fragments[0].clone())], 1));
+
+    let result = client.reassemble_fragments(session_id);
+    assert!(result.is_err_and(|err| err.to_string().contains("length mismatch")));
+}
+
+#[test]
+fn test_peer_payload_encoding_is_negotiated() {
+    let (mut client, _, _, _) = create_test_client();
+    let (ui_tx, _) = unbounded();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+
+    // Client (1) -> Drone (2) -> Server (3)
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(2, NodeType::Drone);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(3, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RequestActiveUsers);
+
+    // Unknown peers get the legacy encoding
+    client.send_message(3, message.clone(), &ui_tx);
+    let packet = packet_2_rx.try_recv().unwrap();
+    assert!(matches!(packet.pack_type, PacketType::MsgFragment(f) if f.data[0] == b'{'));
+
+    // The server answers with a framed CBOR payload
+    let answer = HostMessage::FromServer(ServerToClientMessage::SendingError {
+        error: "test".to_string(),
+        message: ClientToServerMessage::RequestActiveUsers,
+    });
+    let route = SourceRoutingHeader {
+        hop_index: 2,
+        hops: vec![3, 2, 1],
+    };
+    for fragment in client
+        .disassemble_message(&answer, PayloadEncoding::Framed(PayloadCodec::Cbor))
+        .unwrap()
+    {
+        client.handle_message_fragment(&fragment, 99, &route, &ui_tx);
+    }
+    let _ = packet_2_rx.try_iter().count();
+
+    // From now on the server gets framed CBOR payloads
+    client.send_message(3, message, &ui_tx);
+    let packet = packet_2_rx.try_recv().unwrap();
+    match packet.pack_type {
+        PacketType::MsgFragment(fragment) => {
+            assert_eq!(
+                fragment_encoding(&fragment),
+                Some(PayloadEncoding::Framed(PayloadCodec::Cbor))
+            );
+        }
+        _ => panic!("Unexpected packet type"),
+    }
+}
+
+#[test]
+fn test_compressed_payload_roundtrip() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_framing_config(FramingConfig {
+        compression: true,
+        ..FramingConfig::default()
+    });
+    let text = "Hello, drones! ".repeat(100);
+    let message = HostMessage::FromClient(ClientToServerMessage::SendPrivateMessage {
+        recipient_id: 2,
+        message: MessageBody {
+            sender_id: 1,
+            content: MessageContent::Text(text.clone()),
+            timestamp: "12:00".to_string(),
+        },
+    });
+
+    let legacy = client
+        .disassemble_message(&message, PayloadEncoding::Legacy)
+        .unwrap();
+    let fragments = client
+        .disassemble_message(&message, PayloadEncoding::Framed(PayloadCodec::Json))
+        .unwrap();
+
+    assert!(fragments.len() < legacy.len());
+    assert_eq!(client.compression_stats.messages_compressed, 1);
+    assert!(client.compression_stats.fragments_saved > 0);
+    assert!(client.compression_stats.ratio() < 1.0);
+
+    let session_id = 2468;
+    client.pending_received.insert(
+        session_id,
+        (
+            fragments.iter().map(|f| Some(f.clone())).collect(),
+            fragments.len() as u64,
+        ),
+    );
+
+    match client.reassemble_fragments(session_id) {
+        Ok(HostMessage::FromClient(ClientToServerMessage::SendPrivateMessage {
+            message, ..
+        })) => {
+            assert!(matches!(message.content, MessageContent::Text(t) if t == text));
+        }
+        _ => panic!("Unexpected reassembly result"),
+    }
+}
+
+#[test]
+fn test_compression_frames_messages_to_unknown_peers() {
+    let (mut client, _, _, _) = create_test_client();
+    let text = "Hello, drones! ".repeat(100);
+    let message = HostMessage::FromClient(ClientToServerMessage::SendPrivateMessage {
+        recipient_id: 2,
+        message: MessageBody {
+            sender_id: 1,
+            content: MessageContent::Text(text),
+            timestamp: "12:00".to_string(),
+        },
+    });
+
+    // By default a peer that never sent a framed payload gets legacy JSON,
+    // which every node understands
+    let encoding = client.payload_encoding_for(3);
+    assert_eq!(encoding, PayloadEncoding::Legacy);
+    let fragments = client.disassemble_message(&message, encoding).unwrap();
+    assert_eq!(fragments[0].data[0], b'{');
+    assert_eq!(client.compression_stats.messages_compressed, 0);
+
+    let mut client = client.with_framing_config(FramingConfig {
+        compression: true,
+        ..FramingConfig::default()
+    });
+    let encoding = client.payload_encoding_for(3);
+    assert_eq!(encoding, PayloadEncoding::Framed(PayloadCodec::Json));
+    client.disassemble_message(&message, encoding).unwrap();
+    assert_eq!(client.compression_stats.messages_compressed, 1);
+}
+
+#[test]
+fn test_small_payload_is_not_compressed() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_framing_config(FramingConfig {
+        compression: true,
+        ..FramingConfig::default()
+    });
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+
+    let fragments = client
+        .disassemble_message(&message, PayloadEncoding::Framed(PayloadCodec::Json))
+        .unwrap();
+
+    assert_eq!(fragments.len(), 1);
+    // compressed bit of the flags byte of the frame header
+    assert_eq!(fragments[0].data[3] & 0b1, 0);
+    assert_eq!(client.compression_stats.messages_compressed, 0);
+}
+
+#[test]
+fn test_corrupted_payload_fails_checksum() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_framing_config(FramingConfig {
+        compression: false,
+        ..Default::default()
+    });
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+
+    let mut fragments = client
+        .disassemble_message(&message, PayloadEncoding::Framed(PayloadCodec::Json))
+        .unwrap();
+    // Flip a byte of the body, after the header and the checksum
+    let last = fragments[0].length as usize - 1;
+    fragments[0].data[last] ^= 0xFF;
+
+    let session_id = 8766;
+    client
+        .pending_received
+        .insert(session_id, (vec![Some(fragments[0].clone())], 1));
+
+    let result = client.reassemble_fragments(session_id);
+    assert!(matches!(
+        result,
+        Err(ReassemblyError::ChecksumMismatch { expected, computed }) if expected != computed
+    ));
+}
+
+#[test]
+fn test_unknown_frame_version_has_no_encoding() {
+    let (mut client, _, _, _) = create_test_client();
+    let message = HostMessage::FromClient(ClientToServerMessage::RequestActiveUsers);
+    let mut fragments = client
+        .disassemble_message(&message, PayloadEncoding::Framed(PayloadCodec::Cbor))
+        .unwrap();
+    assert_eq!(
+        fragment_encoding(&fragments[0]),
+        Some(PayloadEncoding::Framed(PayloadCodec::Cbor))
+    );
+
+    // version byte of the frame header
+    fragments[0].data[1] += 1;
+    assert_eq!(fragment_encoding(&fragments[0]), None);
+}
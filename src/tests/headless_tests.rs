@@ -0,0 +1,100 @@
+use crate::tests::create_test_client;
+use crate::ui::CLIENTS_STATE;
+use crate::{MessageStatus, OutboxConfig, UiConfig};
+use common_utils::{ClientToServerMessage, HostCommand, ServerToClientMessage};
+use crossbeam_channel::unbounded;
+use std::thread;
+use std::time::{Duration, Instant};
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+
+#[test]
+fn test_client_runs_headless_with_taken_endpoint() {
+    let (client, _, command_tx, _) = create_test_client();
+    let mut client = client.with_outbox_config(OutboxConfig {
+        ttl: Duration::ZERO,
+        ..OutboxConfig::default()
+    });
+    let endpoint = client.take_ui_endpoint().expect("Endpoint already taken");
+    assert!(client.take_ui_endpoint().is_none());
+
+    let handle = thread::spawn(move || client.run());
+
+    // No route to the server is known: the message expires from the outbox
+    // and the failure comes back on the endpoint
+    let message_id = endpoint
+        .to_client
+        .send(
+            3,
+            ClientToServerMessage::RegisterUser {
+                name: "Alice".to_string(),
+            },
+        )
+        .expect("Client not running");
+    let received = endpoint.from_client.recv_timeout(Duration::from_secs(2));
+    assert!(matches!(
+        received,
+        Ok((_, ServerToClientMessage::SendingError { .. }))
+    ));
+    let update = endpoint
+        .delivery_updates
+        .recv_timeout(Duration::from_secs(2))
+        .expect("No delivery update");
+    assert_eq!(update.message_id, message_id);
+    assert_eq!(update.status, MessageStatus::Queued);
+
+    // The web UI was never involved
+    assert!(!CLIENTS_STATE.lock().unwrap().contains_key(&1));
+
+    command_tx.send(HostCommand::Stop).unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_client_without_ui_keeps_handling_packets() {
+    let (client, _, command_tx, packet_tx) = create_test_client();
+    let mut client = client.with_ui_config(UiConfig {
+        enabled: false,
+        ..UiConfig::default()
+    });
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    // The endpoint is not taken, so the client drops it on start
+    let handle = thread::spawn(move || client.run());
+
+    packet_tx
+        .send(Packet {
+            routing_header: SourceRoutingHeader {
+                hop_index: 0,
+                hops: vec![],
+            },
+            session_id: 0,
+            pack_type: PacketType::FloodRequest(FloodRequest {
+                flood_id: 7,
+                initiator_id: 5,
+                path_trace: vec![(5, NodeType::Client), (2, NodeType::Drone)],
+            }),
+        })
+        .unwrap();
+
+    // The client's own discovery floods go out on the same channel
+    let deadline = Instant::now() + Duration::from_secs(2);
+    let mut answered = false;
+    while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+        match packet_2_rx.recv_timeout(timeout) {
+            Ok(packet) => {
+                if matches!(packet.pack_type, PacketType::FloodResponse(ref response) if response.flood_id == 7)
+                {
+                    answered = true;
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    assert!(answered, "No FloodResponse from the client");
+
+    command_tx.send(HostCommand::Stop).unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,147 @@
+use crate::client::history::HistoryStore;
+use crate::tests::create_test_client;
+use crate::{HistoryConfig, HistoryMessage, OutgoingMessage};
+use common_utils::{ClientToServerMessage, MessageBody, MessageContent, ServerToClientMessage};
+use crossbeam_channel::unbounded;
+use std::fs;
+use std::path::PathBuf;
+
+fn history_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("rustbusters-history-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn private_message(sender_id: u8, recipient_id: u8, text: &str) -> ClientToServerMessage {
+    ClientToServerMessage::SendPrivateMessage {
+        recipient_id,
+        message: MessageBody {
+            sender_id,
+            content: MessageContent::Text(text.to_string()),
+            timestamp: "12:00".to_string(),
+        },
+    }
+}
+
+#[test]
+fn test_messages_are_recorded_and_reloaded() {
+    let dir = history_dir("reload");
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_history_config(HistoryConfig {
+        enabled: true,
+        dir: dir.clone(),
+    });
+    client.open_history();
+    let (ui_tx, _) = unbounded();
+
+    client.handle_ui_message(
+        OutgoingMessage {
+            message_id: 1,
+            server_id: 3,
+            message: private_message(1, 5, "hello"),
+        },
+        &ui_tx,
+    );
+    client.handle_ui_message(
+        OutgoingMessage {
+            message_id: 2,
+            server_id: 3,
+            message: ClientToServerMessage::RequestActiveUsers,
+        },
+        &ui_tx,
+    );
+    client.record_received_message(
+        3,
+        &ServerToClientMessage::SendingError {
+            error: "Destination unreachable!".to_string(),
+            message: private_message(1, 6, "lost"),
+        },
+    );
+    drop(client);
+
+    let store = HistoryStore::open(&dir, 1).unwrap();
+    let to_5 = store.page(Some(5), None, 10);
+    assert_eq!(to_5.len(), 1);
+    assert_eq!(to_5[0].seq, 0);
+    assert_eq!(to_5[0].server_id, 3);
+    assert!(matches!(
+        to_5[0].message,
+        HistoryMessage::Sent(ClientToServerMessage::SendPrivateMessage { .. })
+    ));
+
+    // Only chat messages are recorded among the sent ones
+    let all = store.page(None, None, 10);
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[1].peer_id, 6);
+    assert!(matches!(all[1].message, HistoryMessage::Received(_)));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_history_pages_go_backwards() {
+    let dir = history_dir("pages");
+    let mut store = HistoryStore::open(&dir, 1).unwrap();
+    for index in 0..5 {
+        let text = index.to_string();
+        store
+            .append(3, 5, HistoryMessage::Sent(private_message(1, 5, &text)))
+            .unwrap();
+    }
+
+    let latest: Vec<u64> = store.page(None, None, 2).iter().map(|e| e.seq).collect();
+    assert_eq!(latest, vec![3, 4]);
+
+    let older: Vec<u64> = store.page(None, Some(3), 2).iter().map(|e| e.seq).collect();
+    assert_eq!(older, vec![1, 2]);
+
+    // Sequence numbers continue after a reopen
+    drop(store);
+    let mut store = HistoryStore::open(&dir, 1).unwrap();
+    store
+        .append(3, 5, HistoryMessage::Sent(private_message(1, 5, "5")))
+        .unwrap();
+    assert_eq!(store.page(None, None, 1)[0].seq, 5);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_history_is_opt_in() {
+    let (mut client, _, _, _) = create_test_client();
+
+    client.open_history();
+
+    assert!(client.history.is_none());
+}
+
+#[test]
+fn test_received_chat_message_is_filed_under_its_sender() {
+    let dir = history_dir("sender");
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_history_config(HistoryConfig {
+        enabled: true,
+        dir: dir.clone(),
+    });
+    client.open_history();
+
+    client.record_received_message(
+        3,
+        &ServerToClientMessage::PrivateMessage {
+            sender_id: 5,
+            message: MessageBody {
+                sender_id: 5,
+                content: MessageContent::Text("hi".to_string()),
+                timestamp: "12:00".to_string(),
+            },
+        },
+    );
+    drop(client);
+
+    let store = HistoryStore::open(&dir, 1).unwrap();
+    assert_eq!(store.page(Some(5), None, 10).len(), 1);
+    assert!(store.page(Some(3), None, 10).is_empty());
+
+    let _ = fs::remove_dir_all(&dir);
+}
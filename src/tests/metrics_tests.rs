@@ -0,0 +1,128 @@
+use crate::client::metrics::prometheus::{merge_families, render};
+use crate::client::routing::edge_stats::BASE_WEIGHT;
+use crate::tests::create_test_client;
+use crate::{ClientMetrics, OutgoingMessage, RustbustersClient};
+use common_utils::{ClientToServerMessage, HostMessage};
+use crossbeam_channel::{unbounded, Receiver};
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{NackType, NodeType, Packet};
+
+/// Client (1) -> Drone (2) -> Server (3)
+fn setup_line_topology(client: &mut RustbustersClient) -> Receiver<Packet> {
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+    packet_2_rx
+}
+
+#[test]
+fn test_delivered_message_records_latency_and_goodput() {
+    let (mut client, _, _, _) = create_test_client();
+    let packet_2_rx = setup_line_topology(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+
+    client.handle_ack(packet.session_id, 0);
+
+    let metrics = client.metrics();
+    let destination = metrics
+        .destinations
+        .get(&3)
+        .expect("No metrics for the destination");
+    assert_eq!(destination.fragments_sent, 1);
+    assert_eq!(destination.messages_delivered, 1);
+    assert_eq!(destination.latency.count, 1);
+    assert!(destination.goodput_bytes > 0);
+}
+
+#[test]
+fn test_nacks_are_counted_by_type() {
+    let (mut client, _, _, _) = create_test_client();
+    let packet_2_rx = setup_line_topology(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+
+    let nack_header = SourceRoutingHeader {
+        hop_index: 1,
+        hops: vec![2, 1],
+    };
+    client.handle_nack(
+        packet.session_id,
+        0,
+        NackType::Dropped,
+        &nack_header,
+        &ui_tx,
+    );
+
+    let metrics = client.metrics();
+    let destination = metrics.destinations.get(&3).unwrap();
+    assert_eq!(destination.nacks.dropped, 1);
+    assert_eq!(destination.nacks.error_in_routing, 0);
+    assert_eq!(destination.fragments_resent, 1);
+
+    let exposition = metrics.to_prometheus(client.id);
+    assert!(exposition.contains("# TYPE rustbusters_client_nacks_total counter"));
+    assert!(exposition.contains(
+        "rustbusters_client_nacks_total{client=\"1\",destination=\"3\",type=\"dropped\"} 1"
+    ));
+}
+
+#[test]
+fn test_runtime_metrics_are_merged_across_clients() {
+    let (mut client, _, _, _) = create_test_client();
+    let _packet_2_rx = setup_line_topology(&mut client);
+    let (ui_tx, _) = unbounded();
+
+    client.handle_ui_message(
+        OutgoingMessage {
+            message_id: 1,
+            server_id: 3,
+            message: ClientToServerMessage::RegisterUser {
+                name: "Alice".to_string(),
+            },
+        },
+        &ui_tx,
+    );
+    client.publish_ui_snapshots();
+
+    let runtime = &client.metrics().runtime;
+    assert_eq!(runtime.ui_messages_forwarded, 1);
+    assert_eq!(runtime.pending_sent, 1);
+    assert_eq!(runtime.known_nodes, 3);
+
+    let other = ClientMetrics::default();
+    let exposition = render(&merge_families(vec![
+        client.metrics().prometheus_families(1),
+        other.prometheus_families(4),
+    ]));
+
+    assert_eq!(
+        exposition
+            .matches("# TYPE rustbusters_client_known_nodes gauge")
+            .count(),
+        1
+    );
+    assert!(exposition.contains("rustbusters_client_known_nodes{client=\"1\"} 3"));
+    assert!(exposition.contains("rustbusters_client_known_nodes{client=\"4\"} 0"));
+    assert!(exposition.contains("rustbusters_client_ui_messages_forwarded_total{client=\"1\"} 1"));
+    // The route to 3 was computed once
+    assert!(exposition
+        .contains("rustbusters_client_route_cache_lookups_total{client=\"1\",result=\"miss\"} 1"));
+}
@@ -1,7 +1,20 @@
 pub mod commands_tests;
+pub mod congestion_tests;
 pub mod edge_stats_tests;
+pub mod events_tests;
+pub mod flooding_tests;
 pub mod fragmentation_tests;
+pub mod headless_tests;
+pub mod history_tests;
+pub mod metrics_tests;
+pub mod outbox_tests;
+pub mod reassembly_tests;
+pub mod retransmission_tests;
 pub mod routing_tests;
+pub mod topology_aging_tests;
+pub mod topology_tests;
+pub mod ui_config_tests;
+pub mod ui_hub_tests;
 
 use std::collections::HashMap;
 
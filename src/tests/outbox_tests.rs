@@ -0,0 +1,142 @@
+use crate::client::routing::edge_stats::BASE_WEIGHT;
+use crate::tests::create_test_client;
+use crate::{MessageStatus, OutboxConfig};
+use common_utils::{ClientToServerMessage, HostMessage, ServerToClientMessage};
+use crossbeam_channel::unbounded;
+use std::time::Duration;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{NackType, NodeType};
+
+fn register_message() -> HostMessage {
+    HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    })
+}
+
+#[test]
+fn test_queued_message_is_sent_once_a_route_exists() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, ui_rx) = unbounded();
+
+    // Server 3 is not known yet: the message waits in the outbox
+    client.send_message(3, register_message(), &ui_tx);
+    assert!(ui_rx.try_recv().is_err());
+    let snapshot = client.outbox_snapshot();
+    assert_eq!(snapshot.queued.get(&3), Some(&1));
+    assert_eq!(snapshot.messages[0].status, MessageStatus::Queued);
+
+    // Client (1) -> Drone (2) -> Server (3)
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    client.flush_outbox(&ui_tx);
+    let packet = packet_2_rx.try_recv().expect("Queued message was not sent");
+    let snapshot = client.outbox_snapshot();
+    assert!(snapshot.queued.is_empty());
+    assert_eq!(snapshot.messages[0].status, MessageStatus::InFlight);
+
+    client.handle_ack(packet.session_id, 0);
+    assert_eq!(
+        client.outbox_snapshot().messages[0].status,
+        MessageStatus::Delivered
+    );
+}
+
+#[test]
+fn test_flushing_an_unreachable_destination_starts_no_flood() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    client.send_message(3, register_message(), &ui_tx);
+    let _ = packet_2_rx.try_iter().count();
+
+    // Server 3 is still unknown: the message keeps waiting for the next round
+    client.flush_outbox(&ui_tx);
+    assert!(packet_2_rx.try_recv().is_err());
+    assert_eq!(client.outbox_snapshot().queued.get(&3), Some(&1));
+}
+
+#[test]
+fn test_queued_message_expires_after_ttl() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_outbox_config(OutboxConfig {
+        ttl: Duration::ZERO,
+        ..OutboxConfig::default()
+    });
+    let (ui_tx, ui_rx) = unbounded();
+
+    client.send_message(3, register_message(), &ui_tx);
+    client.expire_outbox(&ui_tx);
+
+    assert!(client.outbox.queues.is_empty());
+    assert_eq!(
+        client.outbox_snapshot().messages[0].status,
+        MessageStatus::Failed
+    );
+    assert!(matches!(
+        ui_rx.try_recv(),
+        Ok((_, ServerToClientMessage::SendingError { .. }))
+    ));
+}
+
+#[test]
+fn test_delivery_updates_follow_the_message() {
+    let (mut client, _, _, _) = create_test_client();
+    let endpoint = client.take_ui_endpoint().expect("Endpoint already taken");
+    let (packet_2_tx, _packet_2_rx) = unbounded();
+    let (packet_4_tx, packet_4_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+
+    // Client (1) -> Drone (2) -> Server (3), with Drone (4) as a fallback
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+        known_nodes.insert(4, NodeType::Drone);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.topology.add_edge(1, 4, BASE_WEIGHT * 2.0);
+    client.topology.add_edge(4, 3, BASE_WEIGHT * 2.0);
+    client.packet_send.insert(2, packet_2_tx);
+    client.packet_send.insert(4, packet_4_tx);
+
+    client.send_message(3, register_message(), &ui_tx);
+    let update = endpoint.delivery_updates.try_recv().unwrap();
+    assert_eq!(update.destination, 3);
+    assert_eq!(update.status, MessageStatus::InFlight);
+
+    // Drone 2 crashed: the fragment moves to the route through drone 4
+    let (session_id, fragment_index) = *client.pending_sent.keys().next().unwrap();
+    client.handle_nack(
+        session_id,
+        fragment_index,
+        NackType::ErrorInRouting(2),
+        &SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![2, 1],
+        },
+        &ui_tx,
+    );
+    assert!(packet_4_rx.try_recv().is_ok());
+    let rerouted = endpoint.delivery_updates.try_recv().unwrap();
+    assert_eq!(rerouted.message_id, update.message_id);
+    assert_eq!(rerouted.status, MessageStatus::Rerouted);
+
+    client.handle_ack(session_id, fragment_index);
+    let delivered = endpoint.delivery_updates.try_recv().unwrap();
+    assert_eq!(delivered.message_id, update.message_id);
+    assert_eq!(delivered.status, MessageStatus::Delivered);
+    assert!(endpoint.delivery_updates.try_recv().is_err());
+}
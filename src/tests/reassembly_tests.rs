@@ -0,0 +1,252 @@
+use crate::client::fragmentation::PayloadEncoding;
+use crate::tests::create_test_client;
+use crate::{FramingConfig, PayloadCodec, ReassemblyConfig};
+use common_utils::{ClientToServerMessage, HostMessage, ServerToClientMessage};
+use crossbeam_channel::unbounded;
+use std::time::Duration;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, NackType, PacketType, FRAGMENT_DSIZE};
+
+fn create_fragment(fragment_index: u64, total_n_fragments: u64) -> Fragment {
+    Fragment {
+        fragment_index,
+        total_n_fragments,
+        length: FRAGMENT_DSIZE as u8,
+        data: [b'a'; FRAGMENT_DSIZE],
+    }
+}
+
+fn server_route() -> SourceRoutingHeader {
+    SourceRoutingHeader {
+        hop_index: 2,
+        hops: vec![3, 2, 1],
+    }
+}
+
+#[test]
+fn test_expired_partial_session_is_dropped() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_reassembly_config(ReassemblyConfig {
+        deadline: Duration::ZERO,
+        sweep_interval: Duration::ZERO,
+        ..Default::default()
+    });
+    let (ui_tx, _) = unbounded();
+
+    // Only the first of two fragments arrives
+    client.handle_message_fragment(&create_fragment(0, 2), 42, &server_route(), &ui_tx);
+    assert!(client.pending_received.contains_key(&42));
+
+    assert_eq!(client.sweep_expired_reassemblies(), 1);
+    assert!(client.pending_received.is_empty());
+    assert_eq!(client.expired_reassemblies, 1);
+}
+
+#[test]
+fn test_active_partial_session_is_kept() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_reassembly_config(ReassemblyConfig {
+        deadline: Duration::from_secs(60),
+        sweep_interval: Duration::ZERO,
+        ..Default::default()
+    });
+    let (ui_tx, _) = unbounded();
+
+    client.handle_message_fragment(&create_fragment(0, 2), 42, &server_route(), &ui_tx);
+
+    assert_eq!(client.sweep_expired_reassemblies(), 0);
+    assert!(client.pending_received.contains_key(&42));
+    assert_eq!(client.expired_reassemblies, 0);
+}
+
+fn server_message_fragments() -> Vec<Fragment> {
+    let (mut client, _, _, _) = create_test_client();
+    let message = HostMessage::FromServer(ServerToClientMessage::SendingError {
+        error: "A".repeat(FRAGMENT_DSIZE * 2),
+        message: ClientToServerMessage::UnregisterUser,
+    });
+    client
+        .disassemble_message(&message, PayloadEncoding::Legacy)
+        .unwrap()
+}
+
+#[test]
+fn test_duplicate_fragment_does_not_complete_session() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, ui_rx) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    let fragments = server_message_fragments();
+    assert!(fragments.len() > 2);
+
+    // The first fragment is received twice, the last one is still missing
+    for fragment in fragments.iter().take(fragments.len() - 1) {
+        client.handle_message_fragment(fragment, 42, &server_route(), &ui_tx);
+    }
+    client.handle_message_fragment(&fragments[0], 42, &server_route(), &ui_tx);
+
+    assert!(ui_rx.try_recv().is_err());
+    assert_eq!(client.pending_received[&42].1, fragments.len() as u64 - 1);
+
+    // Every copy is acknowledged
+    assert_eq!(packet_2_rx.len(), fragments.len());
+
+    client.handle_message_fragment(fragments.last().unwrap(), 42, &server_route(), &ui_tx);
+    assert!(ui_rx.try_recv().is_ok());
+}
+
+#[test]
+fn test_late_duplicate_is_acked_but_not_delivered() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, ui_rx) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    let fragments = server_message_fragments();
+    for fragment in &fragments {
+        client.handle_message_fragment(fragment, 42, &server_route(), &ui_tx);
+    }
+    assert_eq!(ui_rx.len(), 1);
+    let _ = packet_2_rx.try_iter().count();
+
+    // A retransmission arrives after the message was delivered
+    client.handle_message_fragment(&fragments[1], 42, &server_route(), &ui_tx);
+
+    assert_eq!(ui_rx.len(), 1);
+    assert!(!client.pending_received.contains_key(&42));
+    match packet_2_rx.try_recv().map(|packet| packet.pack_type) {
+        Ok(PacketType::Ack(ack)) => assert_eq!(ack.fragment_index, 1),
+        _ => panic!("Late duplicate was not acknowledged"),
+    }
+}
+
+#[test]
+fn test_inconsistent_fragments_are_nacked() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    // Index out of range
+    client.handle_message_fragment(&create_fragment(5, 2), 42, &server_route(), &ui_tx);
+    // First fragment announces 2 fragments, the second one announces 3
+    client.handle_message_fragment(&create_fragment(0, 2), 43, &server_route(), &ui_tx);
+    client.handle_message_fragment(&create_fragment(1, 3), 43, &server_route(), &ui_tx);
+
+    let responses: Vec<PacketType> = packet_2_rx.try_iter().map(|p| p.pack_type).collect();
+    assert!(matches!(responses[0], PacketType::Nack(ref nack) if nack.fragment_index == 5));
+    assert!(matches!(responses[1], PacketType::Ack(_)));
+    assert!(matches!(responses[2], PacketType::Nack(ref nack) if nack.fragment_index == 1));
+    assert_eq!(client.pending_received[&43].1, 1);
+}
+
+#[test]
+fn test_too_many_fragments_are_nacked() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    let fragment = create_fragment(0, u64::MAX);
+    client.handle_message_fragment(&fragment, 42, &server_route(), &ui_tx);
+
+    let response = packet_2_rx.try_recv().unwrap();
+    assert!(matches!(
+        response.pack_type,
+        PacketType::Nack(ref nack) if nack.fragment_index == 0 && nack.nack_type == NackType::Dropped
+    ));
+    assert!(!client.pending_received.contains_key(&42));
+}
+
+#[test]
+fn test_corrupted_session_is_nacked_for_retransmission() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_reassembly_config(ReassemblyConfig {
+        retransmit_corrupted: true,
+        ..Default::default()
+    });
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, ui_rx) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    let (sender, _, _, _) = create_test_client();
+    let mut sender = sender.with_framing_config(FramingConfig {
+        compression: false,
+        ..Default::default()
+    });
+    let message = HostMessage::FromServer(ServerToClientMessage::SendingError {
+        error: "A".repeat(FRAGMENT_DSIZE * 2),
+        message: ClientToServerMessage::UnregisterUser,
+    });
+    let fragments = sender
+        .disassemble_message(&message, PayloadEncoding::Framed(PayloadCodec::Json))
+        .unwrap();
+    let total = fragments.len();
+
+    let mut corrupted = fragments.clone();
+    corrupted[1].data[0] ^= 0xFF;
+    for fragment in &corrupted {
+        client.handle_message_fragment(fragment, 42, &server_route(), &ui_tx);
+    }
+
+    assert!(ui_rx.try_recv().is_err());
+    assert_eq!(client.integrity_failures, 1);
+
+    // Every fragment but the last is acked, then a single Nack asks the
+    // session again
+    let responses: Vec<PacketType> = packet_2_rx.try_iter().map(|p| p.pack_type).collect();
+    assert_eq!(responses.len(), total);
+    assert!(responses[..total - 1]
+        .iter()
+        .all(|pack_type| matches!(pack_type, PacketType::Ack(_))));
+    assert!(matches!(
+        &responses[total - 1],
+        PacketType::Nack(nack) if nack.nack_type == NackType::Dropped
+            && nack.fragment_index == total as u64 - 1
+    ));
+
+    // The retransmitted session is not mistaken for a late duplicate
+    for fragment in &fragments {
+        client.handle_message_fragment(fragment, 42, &server_route(), &ui_tx);
+    }
+    assert!(ui_rx.try_recv().is_ok());
+}
+
+#[test]
+fn test_corrupted_session_is_never_acked() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, ui_rx) = unbounded();
+    client.packet_send.insert(2, packet_2_tx);
+
+    let (sender, _, _, _) = create_test_client();
+    let mut sender = sender.with_framing_config(FramingConfig {
+        compression: false,
+        ..Default::default()
+    });
+    let message = HostMessage::FromServer(ServerToClientMessage::SendingError {
+        error: "A".repeat(FRAGMENT_DSIZE * 2),
+        message: ClientToServerMessage::UnregisterUser,
+    });
+    let mut fragments = sender
+        .disassemble_message(&message, PayloadEncoding::Framed(PayloadCodec::Json))
+        .unwrap();
+    let total = fragments.len();
+    fragments[1].data[0] ^= 0xFF;
+    for fragment in &fragments {
+        client.handle_message_fragment(fragment, 42, &server_route(), &ui_tx);
+    }
+
+    // The last fragment completed a corrupted session: it is not acked
+    let responses: Vec<PacketType> = packet_2_rx.try_iter().map(|p| p.pack_type).collect();
+    assert_eq!(responses.len(), total - 1);
+    assert!(responses
+        .iter()
+        .all(|pack_type| matches!(pack_type, PacketType::Ack(_))));
+
+    // Nor is its retransmission
+    client.handle_message_fragment(&fragments[total - 1], 42, &server_route(), &ui_tx);
+    assert!(packet_2_rx.try_recv().is_err());
+    assert!(ui_rx.try_recv().is_err());
+}
@@ -0,0 +1,249 @@
+use crate::client::routing::edge_stats::BASE_WEIGHT;
+use crate::tests::create_test_client;
+use crate::RetransmissionConfig;
+use common_utils::{ClientToServerMessage, HostMessage, ServerToClientMessage};
+use crossbeam_channel::unbounded;
+use std::time::Duration;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{NackType, NodeType, PacketType};
+
+fn immediate_timeout_config(max_retries: u32) -> RetransmissionConfig {
+    RetransmissionConfig {
+        initial_timeout: Duration::ZERO,
+        backoff_factor: 2.0,
+        max_timeout: Duration::ZERO,
+        max_retries,
+    }
+}
+
+#[test]
+fn test_unacked_fragment_is_retransmitted() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_retransmission_config(immediate_timeout_config(3));
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+
+    // Client (1) -> Drone (2) -> Server (3)
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(2, NodeType::Drone);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(3, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+    let original = packet_2_rx.try_recv().expect("No fragment was sent");
+
+    // No Ack arrives: the timer fires and the fragment is sent again
+    client.check_retransmission_timers(&ui_tx);
+    let resent = packet_2_rx
+        .try_recv()
+        .expect("Fragment was not retransmitted");
+
+    assert_eq!(resent.session_id, original.session_id);
+    assert_eq!(resent.routing_header.hops, vec![1, 2, 3]);
+    assert!(matches!(resent.pack_type, PacketType::MsgFragment(_)));
+    assert_eq!(client.pending_sent.len(), 1);
+}
+
+#[test]
+fn test_session_fails_after_retry_budget() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_retransmission_config(immediate_timeout_config(1));
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, ui_rx) = unbounded();
+
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(2, NodeType::Drone);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(3, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+
+    // First expiry consumes the only retry, the second one fails the session
+    client.check_retransmission_timers(&ui_tx);
+    client.check_retransmission_timers(&ui_tx);
+
+    assert_eq!(packet_2_rx.len(), 2);
+    assert!(client.pending_sent.is_empty());
+    assert!(client.pending_session_info.is_empty());
+
+    match ui_rx.try_recv() {
+        Ok((_, ServerToClientMessage::SendingError { message, .. })) => {
+            assert!(matches!(
+                message,
+                ClientToServerMessage::RegisterUser { name } if name == "Alice"
+            ));
+        }
+        _ => panic!("UI was not notified of the failure"),
+    }
+}
+
+#[test]
+fn test_ack_stops_retransmission() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_retransmission_config(immediate_timeout_config(3));
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(2, NodeType::Drone);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(3, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+    let packet = packet_2_rx.try_recv().expect("No fragment was sent");
+
+    client.handle_ack(packet.session_id, 0);
+    client.check_retransmission_timers(&ui_tx);
+
+    assert!(packet_2_rx.try_recv().is_err());
+    assert!(client.pending_session_info.is_empty());
+}
+
+#[test]
+fn test_session_is_resent_on_request() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(2, NodeType::Drone);
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(3, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    // Long enough to need more than one fragment
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "A".repeat(300),
+    });
+    client.send_message(3, message, &ui_tx);
+    let sent: Vec<_> = packet_2_rx.try_iter().collect();
+    assert!(sent.len() > 1);
+    let session_id = sent[0].session_id;
+
+    for fragment_index in 0..sent.len() as u64 - 1 {
+        client.handle_ack(session_id, fragment_index);
+    }
+    assert!(!client.pending_sent.contains_key(&(session_id, 0)));
+
+    // The receiver found the session corrupted and nacks its last fragment
+    let last = sent.len() as u64 - 1;
+    let nack_header = SourceRoutingHeader {
+        hop_index: 1,
+        hops: vec![3, 2, 1],
+    };
+    client.handle_nack(session_id, last, NackType::Dropped, &nack_header, &ui_tx);
+
+    // The acked fragments are sent again with the nacked one
+    let resent: Vec<_> = packet_2_rx.try_iter().collect();
+    assert_eq!(resent.len(), sent.len());
+    assert!(resent.iter().all(|packet| packet.session_id == session_id));
+    assert!(matches!(resent[0].pack_type, PacketType::MsgFragment(ref f) if f.fragment_index == 0));
+    assert!(client.pending_sent.contains_key(&(session_id, 0)));
+}
+
+#[test]
+fn test_session_fails_when_receiver_keeps_asking_it() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_retransmission_config(immediate_timeout_config(2));
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (ui_tx, ui_rx) = unbounded();
+
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "Alice".to_string(),
+    });
+    client.send_message(3, message, &ui_tx);
+    let session_id = packet_2_rx
+        .try_recv()
+        .expect("No fragment was sent")
+        .session_id;
+
+    let nack_header = SourceRoutingHeader {
+        hop_index: 1,
+        hops: vec![3, 2, 1],
+    };
+    for _ in 0..3 {
+        client.handle_nack(session_id, 0, NackType::Dropped, &nack_header, &ui_tx);
+    }
+
+    // Two resends, then the session is given up
+    assert_eq!(packet_2_rx.try_iter().count(), 2);
+    assert!(client.pending_session_info.is_empty());
+    assert!(matches!(
+        ui_rx.try_recv(),
+        Ok((_, ServerToClientMessage::SendingError { .. }))
+    ));
+}
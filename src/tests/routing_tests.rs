@@ -1,6 +1,10 @@
 use crate::client::routing::edge_stats::BASE_WEIGHT;
 use crate::tests::create_test_client;
-use wg_2024::packet::NodeType;
+use crate::MultipathConfig;
+use common_utils::{ClientToServerMessage, HostMessage};
+use crossbeam_channel::unbounded;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{FloodResponse, Fragment, NodeType, Packet, PacketType, FRAGMENT_DSIZE};
 
 #[test]
 fn test_edge_stats_initial_state() {
@@ -291,3 +295,238 @@ fn test_weighted_path_redundant_layers() {
     assert_eq!(path_to_9, Some(vec![1, 3, 6, 9]));
     assert_eq!(path_to_10, Some(vec![1, 4, 7, 10]));
 }
+
+#[test]
+fn test_k_shortest_paths() {
+    let (mut client, _, _, _) = create_test_client();
+
+    // Same topology as test_weighted_path_multiple_options
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    for id in [2, 3, 4, 5] {
+        client
+            .known_nodes
+            .lock()
+            .unwrap()
+            .insert(id, NodeType::Drone);
+    }
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(6, NodeType::Server);
+
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 3, BASE_WEIGHT * 1.2);
+    client.topology.add_edge(3, 4, BASE_WEIGHT * 1.1);
+    client.topology.add_edge(2, 5, BASE_WEIGHT);
+    client.topology.add_edge(5, 4, BASE_WEIGHT);
+    client.topology.add_edge(4, 6, BASE_WEIGHT);
+
+    let paths = client.find_k_shortest_paths(6, 3);
+    // Only two loopless paths exist, ordered by total weight
+    assert_eq!(paths, vec![vec![1, 2, 5, 4, 6], vec![1, 2, 3, 4, 6]]);
+}
+
+#[test]
+fn test_fragments_are_striped_across_routes() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_multipath_config(MultipathConfig {
+        enabled: true,
+        ..Default::default()
+    });
+    let (packet_2_tx, packet_2_rx) = unbounded();
+    let (packet_3_tx, packet_3_rx) = unbounded();
+    let (ui_tx, _) = unbounded();
+
+    // Two equivalent routes:
+    // Client(1) -- Drone(2) -- Server(4)
+    //          \-- Drone(3) --/
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    for id in [2, 3] {
+        client
+            .known_nodes
+            .lock()
+            .unwrap()
+            .insert(id, NodeType::Drone);
+    }
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(4, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 4, BASE_WEIGHT);
+    client.topology.add_edge(1, 3, BASE_WEIGHT);
+    client.topology.add_edge(3, 4, BASE_WEIGHT);
+    client.packet_send.insert(2, packet_2_tx);
+    client.packet_send.insert(3, packet_3_tx);
+
+    let message = HostMessage::FromClient(ClientToServerMessage::RegisterUser {
+        name: "A".repeat(300),
+    });
+    client.send_message(4, message, &ui_tx);
+
+    // Three fragments alternate between the two routes
+    assert_eq!(packet_2_rx.len() + packet_3_rx.len(), 3);
+    assert!(!packet_2_rx.is_empty());
+    assert!(!packet_3_rx.is_empty());
+}
+
+#[test]
+fn test_route_cache_hits_and_invalidation() {
+    let (mut client, _, _, _) = create_test_client();
+
+    // Client (1) -> Drone (2) -> Drone (4) -> Server (3)
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    for id in [2, 4] {
+        client
+            .known_nodes
+            .lock()
+            .unwrap()
+            .insert(id, NodeType::Drone);
+    }
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(3, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 4, BASE_WEIGHT);
+    client.topology.add_edge(4, 3, BASE_WEIGHT);
+
+    assert_eq!(client.find_weighted_path(3), Some(vec![1, 2, 4, 3]));
+    assert_eq!(client.find_weighted_path(3), Some(vec![1, 2, 4, 3]));
+    let stats = client.route_cache_stats();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+
+    // A flood response reveals a shorter route
+    client.handle_flood_response(&FloodResponse {
+        flood_id: 1,
+        path_trace: vec![
+            (1, NodeType::Client),
+            (2, NodeType::Drone),
+            (3, NodeType::Server),
+        ],
+    });
+
+    assert_eq!(client.find_weighted_path(3), Some(vec![1, 2, 3]));
+    let stats = client.route_cache_stats();
+    assert_eq!((stats.misses, stats.invalidations), (2, 1));
+
+    // Known edges in later flood responses leave the cache untouched
+    client.handle_flood_response(&FloodResponse {
+        flood_id: 2,
+        path_trace: vec![
+            (1, NodeType::Client),
+            (2, NodeType::Drone),
+            (3, NodeType::Server),
+        ],
+    });
+    assert_eq!(client.find_weighted_path(3), Some(vec![1, 2, 3]));
+    assert_eq!(client.route_cache_stats().hits, 2);
+}
+
+#[test]
+fn test_route_cache_invalidated_by_weight_drift() {
+    let (mut client, _, _, _) = create_test_client();
+
+    // Client(1) -- Drone(2) -- Server(4)
+    //          \-- Drone(3) --/
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    for id in [2, 3] {
+        client
+            .known_nodes
+            .lock()
+            .unwrap()
+            .insert(id, NodeType::Drone);
+    }
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(4, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 4, BASE_WEIGHT);
+    client.topology.add_edge(1, 3, BASE_WEIGHT * 1.1);
+    client.topology.add_edge(3, 4, BASE_WEIGHT);
+
+    assert_eq!(client.find_weighted_path(4), Some(vec![1, 2, 4]));
+
+    // Repeated drops on 2 -> 4 push its weight past the drift threshold
+    for _ in 0..3 {
+        client.update_edge_stats_on_nack(&[4, 2]);
+    }
+
+    assert_eq!(client.find_weighted_path(4), Some(vec![1, 3, 4]));
+    assert_eq!(client.route_cache_stats().invalidations, 1);
+}
+
+#[test]
+fn test_forced_reroute_bypasses_the_route_cache() {
+    let (mut client, _, _, _) = create_test_client();
+    let (packet_3_tx, packet_3_rx) = unbounded();
+
+    // Client(1) -- Drone(2) -- Server(4)
+    //          \-- Drone(3) --/
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(1, NodeType::Client);
+    for id in [2, 3] {
+        client
+            .known_nodes
+            .lock()
+            .unwrap()
+            .insert(id, NodeType::Drone);
+    }
+    client
+        .known_nodes
+        .lock()
+        .unwrap()
+        .insert(4, NodeType::Server);
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(2, 4, BASE_WEIGHT);
+    client.topology.add_edge(1, 3, BASE_WEIGHT * 1.1);
+    client.topology.add_edge(3, 4, BASE_WEIGHT);
+    client.packet_send.insert(3, packet_3_tx);
+
+    assert_eq!(client.find_weighted_path(4), Some(vec![1, 2, 4]));
+
+    // 2 -> 4 got worse, but not enough to invalidate the whole cache
+    client.topology.add_edge(2, 4, BASE_WEIGHT * 1.2);
+
+    let mut packet = Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index: 0,
+            total_n_fragments: 1,
+            length: 0,
+            data: [0; FRAGMENT_DSIZE],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![1, 2, 4],
+        },
+        session_id: 7,
+    };
+    client.reroute_and_resend(&mut packet, 0, true);
+
+    assert_eq!(packet.routing_header.hops, vec![1, 3, 4]);
+    assert!(packet_3_rx.try_recv().is_ok());
+}
@@ -0,0 +1,82 @@
+use crate::tests::create_test_client;
+use crate::TopologyAgingConfig;
+use wg_2024::packet::{FloodResponse, NodeType};
+
+fn flood_response(flood_id: u64, path_trace: Vec<(u8, NodeType)>) -> FloodResponse {
+    FloodResponse {
+        flood_id,
+        path_trace,
+    }
+}
+
+#[test]
+fn test_unseen_nodes_expire_after_missed_rounds() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_topology_aging_config(TopologyAgingConfig {
+        enabled: true,
+        max_missed_rounds: 2,
+    });
+
+    // Client (1) -> Drone (2) -> Server (3) and Drone (2) -> Server (4)
+    client.handle_flood_response(&flood_response(
+        1,
+        vec![
+            (1, NodeType::Client),
+            (2, NodeType::Drone),
+            (3, NodeType::Server),
+        ],
+    ));
+    client.handle_flood_response(&flood_response(
+        1,
+        vec![
+            (1, NodeType::Client),
+            (2, NodeType::Drone),
+            (4, NodeType::Server),
+        ],
+    ));
+
+    // Server 4 crashes: only server 3 keeps answering the floods
+    for flood_id in 2..=3 {
+        client.start_discovery_round();
+        client.handle_flood_response(&flood_response(
+            flood_id,
+            vec![
+                (1, NodeType::Client),
+                (2, NodeType::Drone),
+                (3, NodeType::Server),
+            ],
+        ));
+    }
+    assert!(client.known_nodes.lock().unwrap().contains_key(&4));
+
+    client.start_discovery_round();
+
+    assert!(!client.known_nodes.lock().unwrap().contains_key(&4));
+    assert!(!client.topology.contains_node(4));
+    assert!(client.known_nodes.lock().unwrap().contains_key(&3));
+    assert!(client.topology.contains_edge(2, 3));
+}
+
+#[test]
+fn test_aging_disabled_keeps_topology() {
+    let (client, _, _, _) = create_test_client();
+    let mut client = client.with_topology_aging_config(TopologyAgingConfig {
+        enabled: false,
+        max_missed_rounds: 1,
+    });
+
+    client.handle_flood_response(&flood_response(
+        1,
+        vec![
+            (1, NodeType::Client),
+            (2, NodeType::Drone),
+            (3, NodeType::Server),
+        ],
+    ));
+    for _ in 0..3 {
+        client.start_discovery_round();
+    }
+
+    assert!(client.topology.contains_edge(2, 3));
+    assert!(client.known_nodes.lock().unwrap().contains_key(&3));
+}
@@ -0,0 +1,105 @@
+use crate::client::routing::edge_stats::BASE_WEIGHT;
+use crate::tests::create_test_client;
+use crate::{EdgeSnapshot, NodeSnapshot, RustbustersClient};
+use wg_2024::packet::NodeType;
+
+/// Client (1) -> Drone (2) -> Server (3)
+fn setup_topology(client: &mut RustbustersClient) {
+    {
+        let mut known_nodes = client.known_nodes.lock().unwrap();
+        known_nodes.insert(1, NodeType::Client);
+        known_nodes.insert(2, NodeType::Drone);
+        known_nodes.insert(3, NodeType::Server);
+    }
+    client.topology.add_edge(1, 2, BASE_WEIGHT);
+    client.topology.add_edge(3, 2, BASE_WEIGHT);
+}
+
+#[test]
+fn test_topology_snapshot() {
+    let (mut client, _, _, _) = create_test_client();
+    setup_topology(&mut client);
+    client.update_edge_stats_on_nack(&[2, 1]);
+
+    let snapshot = client.topology_snapshot();
+
+    assert_eq!(snapshot.client_id, 1);
+    assert_eq!(
+        snapshot.nodes[1],
+        NodeSnapshot {
+            id: 2,
+            node_type: Some(NodeType::Drone)
+        }
+    );
+    assert_eq!(
+        snapshot.edges[1],
+        EdgeSnapshot {
+            from: 2,
+            to: 3,
+            weight: BASE_WEIGHT
+        }
+    );
+    assert!(snapshot.edges[0].weight > BASE_WEIGHT);
+    assert_eq!(snapshot.edge_stats.len(), 1);
+    assert_eq!(
+        (snapshot.edge_stats[0].from, snapshot.edge_stats[0].to),
+        (2, 1)
+    );
+    assert!(snapshot.edge_stats[0].estimated_pdr > 0.0);
+    assert_eq!(snapshot.edge_stats[0].consecutive_nacks, 1);
+
+    let json = serde_json::to_value(&snapshot).unwrap();
+    assert_eq!(json["nodes"][2]["node_type"], "Server");
+}
+
+#[test]
+fn test_topology_dot_export() {
+    let (mut client, _, _, _) = create_test_client();
+    setup_topology(&mut client);
+
+    let dot = client.topology_snapshot().to_dot();
+
+    assert!(dot.starts_with("graph client_1 {"));
+    assert!(dot.contains("3 [label=\"Server 3\", shape=doubleoctagon];"));
+    assert!(dot.contains("2 -- 3 [label=\"1.00\"];"));
+}
+
+#[test]
+fn test_published_topology_snapshot() {
+    let (mut client, _, _, _) = create_test_client();
+    setup_topology(&mut client);
+    assert!(client.shared_topology.lock().unwrap().nodes.is_empty());
+
+    client.publish_ui_snapshots();
+
+    assert_eq!(
+        *client.shared_topology.lock().unwrap(),
+        client.topology_snapshot()
+    );
+}
+
+#[test]
+fn test_edge_stats_snapshot() {
+    let (mut client, _, _, _) = create_test_client();
+    setup_topology(&mut client);
+    client.register_successful_transmission(&[1, 2, 3]);
+    client.register_successful_transmission(&[1, 2, 3]);
+    client.update_edge_stats_on_nack(&[2, 1]);
+
+    let snapshot = client.topology_snapshot();
+    let stats = snapshot
+        .edge_stats
+        .iter()
+        .find(|stats| (stats.from, stats.to) == (2, 3))
+        .unwrap();
+
+    assert_eq!(stats.packets_sent, 2);
+    assert_eq!(stats.consecutive_acks, 2);
+    assert_eq!(stats.consecutive_nacks, 0);
+    assert_eq!(stats.estimated_pdr, 0.0);
+    assert!(stats.confidence > 0.0);
+    assert_eq!(stats.weight, BASE_WEIGHT);
+
+    let json = serde_json::to_value(&snapshot.edge_stats).unwrap();
+    assert!(json[0]["alpha"].is_number());
+}
@@ -0,0 +1,139 @@
+use crate::tests::create_test_client;
+use crate::ui::websocket::{run_websocket_server, stop_websocket_server};
+use crate::UiConfig;
+use crossbeam_channel::bounded;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::Message;
+
+/// Local servers on ports picked by the OS, shared by the tests of the process
+fn local_ui_config() -> UiConfig {
+    UiConfig {
+        bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        http_port: 0,
+        websocket_port: 0,
+        ..UiConfig::default()
+    }
+}
+
+fn http_get(address: SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .write_all(
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .as_bytes(),
+        )
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_ui_servers_bind_ephemeral_ports() {
+    let (client, _, _, _) = create_test_client();
+    let client = client.with_ui_config(local_ui_config());
+
+    let addresses = client.start_ui_servers().expect("UI servers did not start");
+    assert_ne!(addresses.http.port(), 0);
+    assert_ne!(addresses.websocket.port(), 0);
+    // The servers are shared by every client of the process
+    assert_eq!(client.start_ui_servers(), Ok(addresses));
+
+    let response = http_get(addresses.http, "/api/ui-config");
+
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains(&format!(
+        "{{\"websocket_port\":{}}}",
+        addresses.websocket.port()
+    )));
+}
+
+#[test]
+fn test_disabled_ui_does_not_start() {
+    let (client, _, _, _) = create_test_client();
+    let client = client.with_ui_config(UiConfig {
+        enabled: false,
+        ..UiConfig::default()
+    });
+
+    assert!(client.start_ui_servers().is_err());
+}
+
+#[test]
+fn test_static_files_outside_of_the_assets_are_not_served() {
+    let (client, _, _, _) = create_test_client();
+    let client = client.with_ui_config(local_ui_config());
+    let addresses = client.start_ui_servers().expect("UI servers did not start");
+
+    for path in ["//etc/passwd", "/../Cargo.toml", "/assets/../../Cargo.toml"] {
+        let response = http_get(addresses.http, path);
+        assert!(response.starts_with("HTTP/1.1 404"), "{path} was served");
+    }
+}
+
+#[test]
+fn test_different_config_is_rejected_once_servers_run() {
+    let (client, _, _, _) = create_test_client();
+    let client = client.with_ui_config(local_ui_config());
+    client.start_ui_servers().expect("UI servers did not start");
+
+    let (other, _, _, _) = create_test_client();
+    let other = other.with_ui_config(UiConfig {
+        static_dir: "elsewhere".into(),
+        ..local_ui_config()
+    });
+
+    assert!(other.start_ui_servers().is_err());
+}
+
+#[test]
+fn test_websocket_answers_pings_and_requests_on_one_connection() {
+    let (client, _, _, _) = create_test_client();
+    let client = client.with_ui_config(local_ui_config());
+    let addresses = client.start_ui_servers().expect("UI servers did not start");
+
+    let (mut socket, _) =
+        tungstenite::connect(format!("ws://{}", addresses.websocket)).expect("No WebSocket");
+    if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+    }
+    socket.send(Message::Ping(b"ping".to_vec().into())).unwrap();
+    socket
+        .send(Message::Text(
+            r#"{"op": "teleport", "request_id": 3}"#.into(),
+        ))
+        .unwrap();
+
+    let mut pong = false;
+    let mut reply = false;
+    while !(pong && reply) {
+        match socket.read().unwrap() {
+            Message::Pong(payload) => pong = payload.as_ref() == b"ping",
+            Message::Text(text) => reply |= text.contains("\"request_id\":3"),
+            _ => {}
+        }
+    }
+    socket.close(None).unwrap();
+}
+
+#[test]
+fn test_websocket_server_stops() {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let address = listener.local_addr().unwrap();
+    let (stop_tx, stop_rx) = bounded(0);
+    let (done_tx, done_rx) = bounded(1);
+    thread::spawn(move || {
+        run_websocket_server(&listener, &stop_rx);
+        let _ = done_tx.send(());
+    });
+
+    stop_websocket_server(stop_tx, address);
+
+    assert!(done_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+}
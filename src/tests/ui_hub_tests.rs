@@ -0,0 +1,151 @@
+use crate::ui::hub::BroadcastHub;
+use crate::ui::ws_protocol::{handle_ws_request, WsReply};
+use crossbeam_channel::TryRecvError;
+
+#[test]
+fn test_every_connection_receives_every_message() {
+    let hub = BroadcastHub::new(8);
+    let first = hub.subscribe();
+    let second = hub.subscribe();
+
+    assert_eq!(hub.broadcast(1, "hello"), 2);
+    assert_eq!(hub.broadcast(1, "world"), 2);
+
+    for subscription in [&first, &second] {
+        let received: Vec<_> = subscription.receiver.try_iter().collect();
+        assert_eq!(received, vec!["hello".to_string(), "world".to_string()]);
+    }
+}
+
+#[test]
+fn test_slow_connection_is_disconnected() {
+    let hub = BroadcastHub::new(2);
+    let slow = hub.subscribe();
+    let fast = hub.subscribe();
+
+    for index in 0..3 {
+        hub.broadcast(1, &index.to_string());
+        // Only the fast connection keeps draining its queue
+        assert_eq!(fast.receiver.try_recv(), Ok(index.to_string()));
+    }
+
+    assert_eq!(hub.connection_count(), 1);
+    assert_eq!(slow.receiver.try_iter().count(), 2);
+    assert_eq!(slow.receiver.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn test_dropped_subscription_is_removed() {
+    let hub = BroadcastHub::new(8);
+    let subscription = hub.subscribe();
+    assert_eq!(hub.connection_count(), 1);
+
+    drop(subscription);
+
+    assert_eq!(hub.connection_count(), 0);
+    assert_eq!(hub.broadcast(1, "nobody"), 0);
+}
+
+#[test]
+fn test_messages_wait_for_the_first_connection() {
+    let hub = BroadcastHub::new(4);
+
+    for index in 0..3 {
+        hub.broadcast_or_keep(1, &index.to_string());
+    }
+    // Edge statistics are pushed again anyway, they are not kept
+    hub.broadcast(1, "edge stats");
+
+    // Only the most recent messages, half a queue, are kept, for every
+    // connection opened before the client is received
+    let first = hub.subscribe();
+    let second = hub.subscribe();
+    for subscription in [&first, &second] {
+        let received: Vec<_> = subscription.receiver.try_iter().collect();
+        assert_eq!(received, vec!["1".to_string(), "2".to_string()]);
+    }
+
+    hub.broadcast_or_keep(1, "live");
+    let third = hub.subscribe();
+    assert!(third.receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_kept_messages_are_replayed_to_matching_connections() {
+    let hub = BroadcastHub::new(8);
+    let subscription = hub.subscribe();
+    subscription.subscribe_clients(&[1]);
+
+    hub.broadcast_or_keep(1, "from 1");
+    hub.broadcast_or_keep(2, "from 2");
+    assert_eq!(
+        subscription.receiver.try_iter().collect::<Vec<_>>(),
+        vec!["from 1".to_string()]
+    );
+
+    // Client 2 was not received by anyone: its message waits for a filter
+    // that lets it through
+    subscription.subscribe_clients(&[2]);
+    assert_eq!(
+        subscription.receiver.try_iter().collect::<Vec<_>>(),
+        vec!["from 2".to_string()]
+    );
+    subscription.unsubscribe_clients(&[2]);
+    subscription.subscribe_clients(&[2]);
+    assert!(subscription.receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_connection_receives_only_subscribed_clients() {
+    let hub = BroadcastHub::new(8);
+    let subscription = hub.subscribe();
+
+    subscription.subscribe_clients(&[1, 2]);
+    subscription.unsubscribe_clients(&[2]);
+    hub.broadcast(1, "from 1");
+    hub.broadcast(2, "from 2");
+    hub.broadcast(3, "from 3");
+
+    let received: Vec<_> = subscription.receiver.try_iter().collect();
+    assert_eq!(received, vec!["from 1".to_string()]);
+}
+
+#[test]
+fn test_ws_requests_are_acked_or_rejected() {
+    let hub = BroadcastHub::new(8);
+    let subscription = hub.subscribe();
+
+    let reply = handle_ws_request(
+        r#"{"op": "subscribe", "request_id": 1, "client_ids": [5]}"#,
+        &subscription,
+    );
+    assert_eq!(
+        reply,
+        WsReply::Ack {
+            request_id: 1,
+            message_id: None
+        }
+    );
+    assert_eq!(hub.broadcast(6, "from 6"), 0);
+
+    let reply = handle_ws_request(
+        r#"{"op": "register", "request_id": 2, "client_id": 200, "server_id": 3, "username": "Alice"}"#,
+        &subscription,
+    );
+    assert_eq!(
+        reply,
+        WsReply::Error {
+            request_id: Some(2),
+            error: "Unknown client 200".to_string(),
+        }
+    );
+
+    let reply = handle_ws_request(r#"{"op": "teleport", "request_id": 3}"#, &subscription);
+    assert!(matches!(
+        reply,
+        WsReply::Error {
+            request_id: Some(3),
+            ..
+        }
+    ));
+}
@@ -0,0 +1,46 @@
+use crate::ui::CLIENTS_STATE;
+use crate::EdgeStatsSnapshot;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+use wg_2024::network::NodeId;
+
+/// Returns the statistics of every link as seen by the clients
+///
+/// ### Arguments
+/// * `query_params` - HashMap containing query parameters. With an 'id' parameter only
+///   the list of that client is returned, otherwise the lists of all clients keyed by ID
+pub(crate) fn get_edge_stats(
+    query_params: &Option<HashMap<String, String>>,
+) -> Response<Cursor<Vec<u8>>> {
+    let id = query_params.as_ref().and_then(|params| params.get("id"));
+
+    let body = match id {
+        Some(id_str) => {
+            let Ok(id) = id_str.parse::<NodeId>() else {
+                return Response::from_string("Invalid 'id' query parameter").with_status_code(400);
+            };
+            let Some(edge_stats) = clients_edge_stats().remove(&id) else {
+                return Response::from_string(format!("Unknown client {id}")).with_status_code(404);
+            };
+            serde_json::to_string(&edge_stats)
+        }
+        None => serde_json::to_string(&clients_edge_stats()),
+    };
+
+    Response::from_string(body.unwrap_or_default())
+        .with_header(Header::from_str("Content-Type: application/json").unwrap())
+}
+
+/// Collects the last published edge statistics of every client
+pub(crate) fn clients_edge_stats() -> HashMap<NodeId, Vec<EdgeStatsSnapshot>> {
+    let clients_state = CLIENTS_STATE.lock().unwrap();
+    clients_state
+        .iter()
+        .filter_map(|(client_id, client)| {
+            let topology = client.topology.as_ref()?.lock().ok()?;
+            Some((*client_id, topology.edge_stats.clone()))
+        })
+        .collect()
+}
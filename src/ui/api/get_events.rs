@@ -0,0 +1,55 @@
+use crate::ui::CLIENTS_STATE;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+use wg_2024::network::NodeId;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Returns the events recorded in the journal of a client, oldest event first
+///
+/// ### Arguments
+/// * `query_params` - HashMap containing query parameters, must include 'client_id'.
+///   The optional 'after' returns the events newer than that sequence number and
+///   'limit' caps the page size
+pub(crate) fn get_events(
+    query_params: &Option<HashMap<String, String>>,
+) -> Response<Cursor<Vec<u8>>> {
+    let param = |name: &str| query_params.as_ref().and_then(|params| params.get(name));
+
+    let Some(client_id) = param("client_id").and_then(|id| id.parse::<NodeId>().ok()) else {
+        return Response::from_string("Invalid or missing 'client_id' query parameter")
+            .with_status_code(400);
+    };
+
+    let after = match param("after").map(|seq| seq.parse::<u64>()) {
+        None => None,
+        Some(Ok(after)) => Some(after),
+        Some(Err(_)) => {
+            return Response::from_string("Invalid 'after' query parameter").with_status_code(400)
+        }
+    };
+    let limit = match param("limit").map(|limit| limit.parse::<usize>()) {
+        None => DEFAULT_PAGE_SIZE,
+        Some(Ok(limit)) => limit.min(MAX_PAGE_SIZE),
+        Some(Err(_)) => {
+            return Response::from_string("Invalid 'limit' query parameter").with_status_code(400)
+        }
+    };
+
+    let events = CLIENTS_STATE
+        .lock()
+        .unwrap()
+        .get(&client_id)
+        .and_then(|client| client.events.clone());
+
+    let Some(events) = events else {
+        return Response::from_string(format!("Unknown client {client_id}")).with_status_code(404);
+    };
+    let page = events.entries(after, limit);
+
+    Response::from_string(serde_json::to_string(&page).unwrap_or_default())
+        .with_header(Header::from_str("Content-Type: application/json").unwrap())
+}
@@ -0,0 +1,63 @@
+use crate::ui::CLIENTS_STATE;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+use wg_2024::network::NodeId;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+/// Returns a page of the message history of a client, oldest message first
+///
+/// ### Arguments
+/// * `query_params` - HashMap containing query parameters, must include 'client_id'.
+///   The optional 'peer_id' selects a single conversation, 'before' returns the
+///   messages older than that sequence number and 'limit' caps the page size
+pub(crate) fn get_history(
+    query_params: &Option<HashMap<String, String>>,
+) -> Response<Cursor<Vec<u8>>> {
+    let param = |name: &str| query_params.as_ref().and_then(|params| params.get(name));
+
+    let Some(client_id) = param("client_id").and_then(|id| id.parse::<NodeId>().ok()) else {
+        return Response::from_string("Invalid or missing 'client_id' query parameter")
+            .with_status_code(400);
+    };
+
+    let peer_id = match param("peer_id").map(|id| id.parse::<NodeId>()) {
+        None => None,
+        Some(Ok(peer_id)) => Some(peer_id),
+        Some(Err(_)) => {
+            return Response::from_string("Invalid 'peer_id' query parameter").with_status_code(400)
+        }
+    };
+    let before = match param("before").map(|seq| seq.parse::<u64>()) {
+        None => None,
+        Some(Ok(before)) => Some(before),
+        Some(Err(_)) => {
+            return Response::from_string("Invalid 'before' query parameter").with_status_code(400)
+        }
+    };
+    let limit = match param("limit").map(|limit| limit.parse::<usize>()) {
+        None => DEFAULT_PAGE_SIZE,
+        Some(Ok(limit)) => limit.min(MAX_PAGE_SIZE),
+        Some(Err(_)) => {
+            return Response::from_string("Invalid 'limit' query parameter").with_status_code(400)
+        }
+    };
+
+    let history = CLIENTS_STATE
+        .lock()
+        .unwrap()
+        .get(&client_id)
+        .and_then(|client| client.history.clone());
+
+    let Some(history) = history else {
+        return Response::from_string(format!("No history for client {client_id}"))
+            .with_status_code(404);
+    };
+    let page = history.lock().unwrap().page(peer_id, before, limit);
+
+    Response::from_string(serde_json::to_string(&page).unwrap_or_default())
+        .with_header(Header::from_str("Content-Type: application/json").unwrap())
+}
@@ -0,0 +1,52 @@
+use crate::ui::CLIENTS_STATE;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+use wg_2024::network::NodeId;
+
+/// Returns the per-destination delivery metrics of a specific client
+///
+/// ### Arguments
+/// * `query_params` - HashMap containing query parameters, must include 'id' parameter for client identification.
+///   The optional 'format' parameter selects `json` (default) or `prometheus` output
+pub(crate) fn get_client_metrics(
+    query_params: &Option<HashMap<String, String>>,
+) -> Response<Cursor<Vec<u8>>> {
+    // Get the 'id' parameter from query string
+    let Some(id) = query_params
+        .as_ref()
+        .and_then(|params| params.get("id"))
+        .and_then(|id_str| id_str.parse::<NodeId>().ok())
+    else {
+        return Response::from_string("Invalid or missing 'id' query parameter")
+            .with_status_code(400);
+    };
+
+    let format = query_params
+        .as_ref()
+        .and_then(|params| params.get("format"))
+        .map_or("json", String::as_str);
+
+    let metrics = CLIENTS_STATE
+        .lock()
+        .unwrap()
+        .get(&id)
+        .and_then(|client| client.metrics.clone());
+
+    let Some(metrics) = metrics else {
+        return Response::from_string(format!("Unknown client {id}")).with_status_code(404);
+    };
+    let metrics = metrics.lock().unwrap().clone();
+
+    match format {
+        "json" => Response::from_string(serde_json::to_string(&metrics).unwrap_or_default())
+            .with_header(Header::from_str("Content-Type: application/json").unwrap()),
+        "prometheus" => Response::from_string(metrics.to_prometheus(id))
+            .with_header(Header::from_str("Content-Type: text/plain; version=0.0.4").unwrap()),
+        _ => Response::from_string(
+            "Invalid 'format' query parameter, expected 'json' or 'prometheus'",
+        )
+        .with_status_code(400),
+    }
+}
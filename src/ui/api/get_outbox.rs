@@ -0,0 +1,39 @@
+use crate::ui::CLIENTS_STATE;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+use wg_2024::network::NodeId;
+
+/// Returns the messages a client queued for unreachable destinations and
+/// the delivery status of its recent messages
+///
+/// ### Arguments
+/// * `query_params` - HashMap containing query parameters, must include 'id' parameter for client identification
+pub(crate) fn get_outbox(
+    query_params: &Option<HashMap<String, String>>,
+) -> Response<Cursor<Vec<u8>>> {
+    // Get the 'id' parameter from query string
+    let Some(id) = query_params
+        .as_ref()
+        .and_then(|params| params.get("id"))
+        .and_then(|id_str| id_str.parse::<NodeId>().ok())
+    else {
+        return Response::from_string("Invalid or missing 'id' query parameter")
+            .with_status_code(400);
+    };
+
+    let outbox = CLIENTS_STATE
+        .lock()
+        .unwrap()
+        .get(&id)
+        .and_then(|client| client.outbox.clone());
+
+    let Some(outbox) = outbox else {
+        return Response::from_string(format!("Unknown client {id}")).with_status_code(404);
+    };
+    let outbox = outbox.lock().unwrap().clone();
+
+    Response::from_string(serde_json::to_string(&outbox).unwrap_or_default())
+        .with_header(Header::from_str("Content-Type: application/json").unwrap())
+}
@@ -0,0 +1,23 @@
+use crate::client::metrics::prometheus::{merge_families, render};
+use crate::ui::CLIENTS_STATE;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+
+/// Returns the metrics of every client in the Prometheus text exposition format
+pub(crate) fn get_prometheus_metrics() -> Response<Cursor<Vec<u8>>> {
+    let clients: Vec<_> = CLIENTS_STATE
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|(id, client)| Some((*id, client.metrics.clone()?)))
+        .collect();
+
+    let mut families = Vec::with_capacity(clients.len());
+    for (id, metrics) in clients {
+        families.push(metrics.lock().unwrap().prometheus_families(id));
+    }
+
+    Response::from_string(render(&merge_families(families)))
+        .with_header(Header::from_str("Content-Type: text/plain; version=0.0.4").unwrap())
+}
@@ -44,7 +44,7 @@ pub(crate) fn get_registered_users(
         let message = ClientToServerMessage::RequestActiveUsers;
 
         // send the message to the client node
-        sender.send((server_id, message)).ok();
+        sender.send(server_id, message);
     }
 
     // Response OK 200 with sample text
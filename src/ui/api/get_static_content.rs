@@ -2,20 +2,30 @@ use crate::ui::utils::get_mime_type;
 use log::{info, warn};
 use std::fs;
 use std::io::Cursor;
+use std::path::{Component, Path};
 use std::str::FromStr;
 use tiny_http::{Header, Response};
 
-const STATIC_PATH: &str = "static/client/frontend/client-build";
-
 /// Serves static files from the filesystem
 ///
 /// ### Arguments
+/// * `static_dir` - Directory the assets of the frontend are served from
 /// * `path` - The requested file path
 ///
 /// Returns an HTTP response containing the file content or an error
-pub(crate) fn provide_static_file(path: &str) -> Response<Cursor<Vec<u8>>> {
+pub(crate) fn provide_static_file(static_dir: &Path, path: &str) -> Response<Cursor<Vec<u8>>> {
     let sanitized_path = &path[1..]; // Remove initial slash
-    match fs::read(format!("{STATIC_PATH}/{sanitized_path}")) {
+
+    // Joining an absolute path would replace `static_dir`, and `..` would escape it
+    if !Path::new(sanitized_path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        warn!("[CLIENT-HTTP] Rejecting path outside of the assets: {sanitized_path}");
+        return Response::from_string("404 Not Found").with_status_code(404);
+    }
+
+    match fs::read(static_dir.join(sanitized_path)) {
         Ok(content) => {
             info!("[CLIENT-HTTP] Serving static file: {sanitized_path}");
             Response::from_data(content).with_header(
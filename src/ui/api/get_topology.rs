@@ -0,0 +1,51 @@
+use crate::ui::CLIENTS_STATE;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+use wg_2024::network::NodeId;
+
+/// Returns the topology known by a specific client, with node types, edge
+/// weights and edge statistics
+///
+/// ### Arguments
+/// * `query_params` - HashMap containing query parameters, must include 'id' parameter for client identification.
+///   The optional 'format' parameter selects `json` (default) or `dot` output
+pub(crate) fn get_topology(
+    query_params: &Option<HashMap<String, String>>,
+) -> Response<Cursor<Vec<u8>>> {
+    // Get the 'id' parameter from query string
+    let Some(id) = query_params
+        .as_ref()
+        .and_then(|params| params.get("id"))
+        .and_then(|id_str| id_str.parse::<NodeId>().ok())
+    else {
+        return Response::from_string("Invalid or missing 'id' query parameter")
+            .with_status_code(400);
+    };
+
+    let format = query_params
+        .as_ref()
+        .and_then(|params| params.get("format"))
+        .map_or("json", String::as_str);
+
+    let topology = CLIENTS_STATE
+        .lock()
+        .unwrap()
+        .get(&id)
+        .and_then(|client| client.topology.clone());
+
+    let Some(topology) = topology else {
+        return Response::from_string(format!("Unknown client {id}")).with_status_code(404);
+    };
+    let snapshot = topology.lock().unwrap().clone();
+
+    match format {
+        "json" => Response::from_string(serde_json::to_string(&snapshot).unwrap_or_default())
+            .with_header(Header::from_str("Content-Type: application/json").unwrap()),
+        "dot" => Response::from_string(snapshot.to_dot())
+            .with_header(Header::from_str("Content-Type: text/vnd.graphviz").unwrap()),
+        _ => Response::from_string("Invalid 'format' query parameter, expected 'json' or 'dot'")
+            .with_status_code(400),
+    }
+}
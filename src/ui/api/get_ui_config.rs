@@ -0,0 +1,17 @@
+use crate::ui::UI_ADDRESSES;
+use std::io::Cursor;
+use std::str::FromStr;
+use tiny_http::{Header, Response};
+
+/// Returns the port of the WebSocket server, for the frontend to connect to
+/// it also when the port was picked by the OS
+pub(crate) fn get_ui_config() -> Response<Cursor<Vec<u8>>> {
+    let Some(addresses) = *UI_ADDRESSES.lock().unwrap() else {
+        return Response::from_string("UI servers not started").with_status_code(503);
+    };
+
+    Response::from_string(
+        serde_json::json!({ "websocket_port": addresses.websocket.port() }).to_string(),
+    )
+    .with_header(Header::from_str("Content-Type: application/json").unwrap())
+}
@@ -1,7 +1,15 @@
 pub(crate) mod get_clients;
+pub(crate) mod get_edge_stats;
+pub(crate) mod get_events;
+pub(crate) mod get_history;
+pub(crate) mod get_metrics;
+pub(crate) mod get_outbox;
+pub(crate) mod get_prometheus_metrics;
 pub(crate) mod get_registered_users;
 pub(crate) mod get_servers;
 pub(crate) mod get_static_content;
+pub(crate) mod get_topology;
+pub(crate) mod get_ui_config;
 pub(crate) mod post_register;
 pub(crate) mod post_send_message;
 pub(crate) mod post_unregister;
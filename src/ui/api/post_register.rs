@@ -35,7 +35,7 @@ pub(crate) fn post_register(req: &mut Request) -> Response<Cursor<Vec<u8>>> {
         };
 
         // send the message to the client node
-        sender.send((server_id.unwrap(), message)).ok();
+        sender.send(server_id.unwrap(), message);
     }
 
     Response::from_string("Register request received").with_status_code(200)
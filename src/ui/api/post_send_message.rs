@@ -5,13 +5,14 @@ use crate::ui::CLIENTS_STATE;
 use common_utils::{ClientToServerMessage, MessageBody};
 use serde_json::Value;
 use std::io::Cursor;
-use tiny_http::{Request, Response};
+use std::str::FromStr;
+use tiny_http::{Header, Request, Response};
 
 /// Processes a message sending request between users
-/// 
+///
 /// ### Arguments
 /// * `req` - The HTTP request containing the message details
-/// 
+///
 /// Returns an HTTP response indicating the result of the operation
 pub(crate) fn post_send_message(req: &mut Request) -> Response<Cursor<Vec<u8>>> {
     // get the body of the request
@@ -41,20 +42,26 @@ pub(crate) fn post_send_message(req: &mut Request) -> Response<Cursor<Vec<u8>>>
         .get(&sender_id.unwrap())
         .and_then(|client| client.sender.clone());
 
-    if let Some(sender) = client_sender {
-        // build the message
-        let message = ClientToServerMessage::SendPrivateMessage {
-            recipient_id: receiver_id.unwrap(),
-            message: MessageBody {
-                sender_id: sender_id.unwrap(),
-                content: content.unwrap(),
-                timestamp: timestamp.unwrap(),
-            },
-        };
+    let Some(sender) = client_sender else {
+        return Response::from_string("Unknown client").with_status_code(404);
+    };
 
-        // send the message to the client node
-        sender.send((server_id.unwrap(), message)).ok();
-    }
+    // build the message
+    let message = ClientToServerMessage::SendPrivateMessage {
+        recipient_id: receiver_id.unwrap(),
+        message: MessageBody {
+            sender_id: sender_id.unwrap(),
+            content: content.unwrap(),
+            timestamp: timestamp.unwrap(),
+        },
+    };
 
-    Response::from_string("Message sent").with_status_code(200)
+    // send the message to the client node, its ID tracks the delivery status
+    match sender.send(server_id.unwrap(), message) {
+        Some(message_id) => {
+            Response::from_string(serde_json::json!({ "message_id": message_id }).to_string())
+                .with_header(Header::from_str("Content-Type: application/json").unwrap())
+        }
+        None => Response::from_string("Client not running").with_status_code(503),
+    }
 }
@@ -32,7 +32,7 @@ pub(crate) fn post_unregister(req: &mut Request) -> Response<Cursor<Vec<u8>>> {
         let message = ClientToServerMessage::UnregisterUser;
 
         // send the message to the client node
-        sender.send((server_id.unwrap(), message)).ok();
+        sender.send(server_id.unwrap(), message);
     }
 
     Response::from_string("Unregister request received").with_status_code(200)
@@ -0,0 +1,59 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+const DEFAULT_HTTP_PORT: u16 = 7373;
+const DEFAULT_WEBSOCKET_PORT: u16 = DEFAULT_HTTP_PORT + 1;
+const DEFAULT_STATIC_DIR: &str = "static/client/frontend/client-build";
+
+/// Binding of the HTTP and WebSocket servers of the UI.
+///
+/// The servers are shared by all the clients of the process: the first
+/// client with the UI enabled starts them with its configuration, and the
+/// configuration of the other clients must match it.
+#[derive(Debug, Clone)]
+pub struct UiConfig {
+    /// When disabled the client neither starts nor registers with the UI servers
+    pub enabled: bool,
+    pub bind_address: IpAddr,
+    /// Port of the HTTP server, 0 to let the OS pick a free one
+    pub http_port: u16,
+    /// Port of the WebSocket server, 0 to let the OS pick a free one
+    pub websocket_port: u16,
+    /// Directory the assets of the frontend are served from
+    pub static_dir: PathBuf,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            http_port: DEFAULT_HTTP_PORT,
+            websocket_port: DEFAULT_WEBSOCKET_PORT,
+            static_dir: PathBuf::from(DEFAULT_STATIC_DIR),
+        }
+    }
+}
+
+impl UiConfig {
+    /// Whether servers started with another configuration can serve this one
+    ///
+    /// ### Arguments
+    /// * `running` - The configuration the servers were started with
+    /// * `addresses` - The addresses the servers are bound to
+    pub(crate) fn is_served_by(&self, running: &UiConfig, addresses: &UiAddresses) -> bool {
+        // Port 0 accepts whatever port the OS picked
+        let port_matches = |port: u16, bound: SocketAddr| port == 0 || port == bound.port();
+        self.bind_address == running.bind_address
+            && port_matches(self.http_port, addresses.http)
+            && port_matches(self.websocket_port, addresses.websocket)
+            && self.static_dir == running.static_dir
+    }
+}
+
+/// Addresses the UI servers are actually bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiAddresses {
+    pub http: SocketAddr,
+    pub websocket: SocketAddr,
+}
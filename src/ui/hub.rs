@@ -0,0 +1,262 @@
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use wg_2024::network::NodeId;
+
+/// Messages that may wait for a single WebSocket connection before it is
+/// considered too slow and disconnected
+const CONNECTION_QUEUE_CAPACITY: usize = 256;
+
+lazy_static! {
+    pub(crate) static ref HUB: BroadcastHub = BroadcastHub::new(CONNECTION_QUEUE_CAPACITY);
+}
+
+/// Fans out every message to all the subscribed WebSocket connections,
+/// each one with its own bounded queue
+#[derive(Debug)]
+pub(crate) struct BroadcastHub {
+    capacity: usize,
+    next_id: AtomicU64,
+    connections: Mutex<HashMap<u64, Connection>>,
+    // Messages of each client sent while no connection received the client,
+    // replayed to the connections that come to receive it.
+    // Always locked after `connections`.
+    backlog: Mutex<HashMap<NodeId, VecDeque<String>>>,
+}
+
+/// Clients whose messages a connection receives
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ClientFilter {
+    /// Every client but the excluded ones, the default of new connections
+    All { excluded: HashSet<NodeId> },
+    /// Only the listed clients
+    Only(HashSet<NodeId>),
+}
+
+impl ClientFilter {
+    pub(crate) fn allows(&self, client_id: NodeId) -> bool {
+        match self {
+            ClientFilter::All { excluded } => !excluded.contains(&client_id),
+            ClientFilter::Only(clients) => clients.contains(&client_id),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Connection {
+    queue: Sender<String>,
+    filter: ClientFilter,
+    // Clients whose kept messages were already replayed to the connection
+    replayed: HashSet<NodeId>,
+}
+
+impl Connection {
+    /// Queues the kept messages of the clients the connection receives,
+    /// once per client
+    ///
+    /// ### Arguments
+    /// * `id` - The ID of the connection
+    /// * `backlog` - The kept messages of each client
+    fn replay(&mut self, id: u64, backlog: &HashMap<NodeId, VecDeque<String>>) {
+        for (client_id, messages) in backlog {
+            if !self.filter.allows(*client_id) || !self.replayed.insert(*client_id) {
+                continue;
+            }
+            for (index, message) in messages.iter().enumerate() {
+                if self.queue.try_send(message.clone()).is_err() {
+                    warn!(
+                        "[CLIENT-WS] Connection {id} has no room, dropping {} kept messages of client {client_id}",
+                        messages.len() - index
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Queue of the messages waiting to be written to a connection.
+/// The connection is removed from the hub when this is dropped.
+pub(crate) struct Subscription<'a> {
+    hub: &'a BroadcastHub,
+    pub(crate) id: u64,
+    pub(crate) receiver: Receiver<String>,
+}
+
+impl Drop for Subscription<'_> {
+    fn drop(&mut self) {
+        self.hub.unsubscribe(self.id);
+    }
+}
+
+impl Subscription<'_> {
+    /// Receives the messages of the given clients. The first call restricts
+    /// the connection, which received every client until then, to them.
+    ///
+    /// ### Arguments
+    /// * `client_ids` - The clients to subscribe to
+    pub(crate) fn subscribe_clients(&self, client_ids: &[NodeId]) {
+        self.hub.update_filter(self.id, |filter| match filter {
+            ClientFilter::All { .. } => {
+                *filter = ClientFilter::Only(client_ids.iter().copied().collect());
+            }
+            ClientFilter::Only(clients) => clients.extend(client_ids),
+        });
+    }
+
+    /// Stops receiving the messages of the given clients
+    ///
+    /// ### Arguments
+    /// * `client_ids` - The clients to unsubscribe from
+    pub(crate) fn unsubscribe_clients(&self, client_ids: &[NodeId]) {
+        self.hub.update_filter(self.id, |filter| match filter {
+            ClientFilter::All { excluded } => excluded.extend(client_ids),
+            ClientFilter::Only(clients) => clients.retain(|id| !client_ids.contains(id)),
+        });
+    }
+}
+
+impl BroadcastHub {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_id: AtomicU64::new(0),
+            connections: Mutex::new(HashMap::new()),
+            backlog: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a new connection, handing it the messages kept while no
+    /// connection received their client
+    pub(crate) fn subscribe(&self) -> Subscription<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (queue, receiver) = bounded(self.capacity);
+        let mut connection = Connection {
+            queue,
+            filter: ClientFilter::All {
+                excluded: HashSet::new(),
+            },
+            replayed: HashSet::new(),
+        };
+        let mut connections = self.connections.lock().unwrap();
+        connection.replay(id, &self.backlog.lock().unwrap());
+        connections.insert(id, connection);
+        info!("[CLIENT-WS] Connection {id} subscribed");
+
+        Subscription {
+            hub: self,
+            id,
+            receiver,
+        }
+    }
+
+    /// Removes a connection, closing its queue
+    ///
+    /// ### Arguments
+    /// * `id` - The ID of the connection
+    pub(crate) fn unsubscribe(&self, id: u64) {
+        if self.connections.lock().unwrap().remove(&id).is_some() {
+            info!("[CLIENT-WS] Connection {id} unsubscribed");
+        }
+    }
+
+    /// Queues a message of a client for every connection subscribed to it.
+    ///
+    /// Connections whose queue is full are too slow to keep up: they are
+    /// removed from the hub, and find their queue disconnected once drained.
+    ///
+    /// ### Arguments
+    /// * `client_id` - The client the message belongs to
+    /// * `message` - The message to deliver
+    ///
+    /// ### Returns
+    /// The number of connections the message was queued for
+    pub(crate) fn broadcast(&self, client_id: NodeId, message: &str) -> usize {
+        queue_message(&mut self.connections.lock().unwrap(), client_id, message)
+    }
+
+    /// Queues a message of a client like `broadcast`, keeping it for the
+    /// connections to come while none receives the client.
+    ///
+    /// Only the most recent messages of each client are kept, half a
+    /// connection queue, so that a new connection has room for the ones
+    /// broadcast meanwhile.
+    ///
+    /// ### Arguments
+    /// * `client_id` - The client the message belongs to
+    /// * `message` - The message to deliver
+    pub(crate) fn broadcast_or_keep(&self, client_id: NodeId, message: &str) {
+        let mut connections = self.connections.lock().unwrap();
+        let mut backlog = self.backlog.lock().unwrap();
+        if queue_message(&mut connections, client_id, message) > 0 {
+            // The kept messages were replayed to the connections receiving the client
+            backlog.remove(&client_id);
+            return;
+        }
+
+        let kept = backlog.entry(client_id).or_default();
+        if kept.len() >= self.capacity / 2 {
+            kept.pop_front();
+            warn!("[CLIENT-WS] No connection receives client {client_id}, dropping its oldest kept message");
+        }
+        kept.push_back(message.to_string());
+    }
+
+    /// Updates the clients a connection receives the messages of
+    ///
+    /// ### Arguments
+    /// * `id` - The ID of the connection
+    /// * `update` - Change to apply to the filter of the connection
+    fn update_filter(&self, id: u64, update: impl FnOnce(&mut ClientFilter)) {
+        if let Some(connection) = self.connections.lock().unwrap().get_mut(&id) {
+            update(&mut connection.filter);
+            // The clients the connection now receives may have kept messages
+            connection.replay(id, &self.backlog.lock().unwrap());
+        }
+    }
+
+    /// Removes every connection, closing their queues
+    pub(crate) fn disconnect_all(&self) {
+        self.connections.lock().unwrap().clear();
+    }
+
+    /// Number of subscribed connections
+    pub(crate) fn connection_count(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
+}
+
+/// Queues a message of a client for every connection subscribed to it,
+/// removing the ones whose queue is full
+///
+/// ### Returns
+/// The number of connections the message was queued for
+fn queue_message(
+    connections: &mut HashMap<u64, Connection>,
+    client_id: NodeId,
+    message: &str,
+) -> usize {
+    let mut delivered = 0;
+
+    connections.retain(|id, connection| {
+        if !connection.filter.allows(client_id) {
+            return true;
+        }
+        match connection.queue.try_send(message.to_string()) {
+            Ok(()) => {
+                delivered += 1;
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!("[CLIENT-WS] Connection {id} is too slow, disconnecting it");
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    });
+
+    delivered
+}
@@ -1,31 +1,47 @@
 mod api;
+mod config;
+pub(crate) mod hub;
 mod request_handler;
 mod utils;
-mod websocket;
+pub(crate) mod websocket;
+pub(crate) mod ws_protocol;
 
-use crate::RustbustersClient;
-use common_utils::{ClientToServerMessage, ServerToClientMessage};
-use crossbeam_channel::{Receiver, Sender};
+pub use config::{UiAddresses, UiConfig};
+
+use crate::client::history::HistoryStore;
+use crate::ui::hub::HUB;
+use crate::{
+    ClientMetrics, EventJournalReader, MessageSender, OutboxSnapshot, RustbustersClient,
+    TopologySnapshot, UiEndpoint,
+};
+use crossbeam_channel::Sender;
 use lazy_static::lazy_static;
-use log::info;
+use log::{error, info};
 use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tiny_http::Server;
 use wg_2024::network::NodeId;
 use wg_2024::packet::NodeType;
 
-const HTTP_PORT: u16 = 7373;
-
 type KnownNodes = Option<Arc<Mutex<HashMap<NodeId, NodeType>>>>;
+type SharedTopology = Option<Arc<Mutex<TopologySnapshot>>>;
+type SharedMetrics = Option<Arc<Mutex<ClientMetrics>>>;
+type SharedHistory = Option<Arc<Mutex<HistoryStore>>>;
+type SharedOutbox = Option<Arc<Mutex<OutboxSnapshot>>>;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ClientState {
     known_nodes: KnownNodes,
+    topology: SharedTopology,
+    metrics: SharedMetrics,
+    history: SharedHistory,
+    outbox: SharedOutbox,
+    events: Option<EventJournalReader>,
     // NodeId is the destination server
-    sender: Option<Sender<(NodeId, ClientToServerMessage)>>,
-    // NodeId is the id of the destination client
-    receiver: Option<Receiver<(NodeId, ServerToClientMessage)>>,
+    sender: Option<MessageSender>,
 }
 
 lazy_static! {
@@ -37,62 +53,169 @@ lazy_static! {
         Mutex::new(HashMap::new());
 }
 
+lazy_static! {
+    // Set while the servers run, they then serve the whole process
+    pub(crate) static ref UI_ADDRESSES: Mutex<Option<UiAddresses>> = Mutex::new(None);
+    // Always locked after UI_ADDRESSES, which is held while clients join or
+    // leave so that none joins servers being stopped
+    static ref UI_SERVERS: Mutex<Option<RunningUiServers>> = Mutex::new(None);
+}
+
+/// The running UI servers, with what is needed to stop them
+struct RunningUiServers {
+    // Configuration of the client that started the servers
+    config: UiConfig,
+    http_server: Arc<Server>,
+    // Dropped to stop the WebSocket server
+    websocket_stop: Sender<()>,
+}
+
 impl RustbustersClient {
-    pub(crate) fn run_ui(
+    /// Starts the HTTP and WebSocket servers of the UI, unless another client
+    /// already did, and returns the addresses they are bound to.
+    ///
+    /// Fails if the servers were started by another client with a different
+    /// binding or assets directory.
+    ///
+    /// Can be called before `run` to learn the ports picked by the OS when
+    /// the configuration asks for port 0.
+    pub fn start_ui_servers(&self) -> Result<UiAddresses, String> {
+        self.start_ui_servers_locked(&mut UI_ADDRESSES.lock().unwrap())
+    }
+
+    /// Starts the UI servers, unless they already run with the same configuration
+    ///
+    /// ### Arguments
+    /// * `ui_addresses` - The addresses of the running servers, already locked
+    fn start_ui_servers_locked(
         &self,
-        sender: Sender<(NodeId, ClientToServerMessage)>,
-        receiver: Receiver<(NodeId, ServerToClientMessage)>,
-    ) {
-        // log the content of Clients
-        let mut clients_state = CLIENTS_STATE.lock().unwrap();
-
-        // if it is empty, run the http server
-        if clients_state.is_empty() {
-            let http_handle = thread::spawn(run_http_server);
-            let websocket_handle = thread::spawn(websocket::run_websocket_server);
-
-            let mut threads = THREADS.lock().unwrap();
-            threads.push(http_handle);
-            threads.push(websocket_handle);
+        ui_addresses: &mut Option<UiAddresses>,
+    ) -> Result<UiAddresses, String> {
+        if !self.ui_config.enabled {
+            return Err(format!("Client {}: UI is disabled", self.id));
+        }
+
+        let mut ui_servers = UI_SERVERS.lock().unwrap();
+        if let (Some(addresses), Some(running)) = (*ui_addresses, ui_servers.as_ref()) {
+            if !self.ui_config.is_served_by(&running.config, &addresses) {
+                return Err(format!(
+                    "Client {}: UI servers already running on {} and {} with a different configuration",
+                    self.id, addresses.http, addresses.websocket
+                ));
+            }
+            return Ok(addresses);
+        }
+
+        let config = &self.ui_config;
+        let http_server = Server::http((config.bind_address, config.http_port))
+            .map_err(|e| format!("Unable to bind the HTTP server: {e}"))?;
+        let http = http_server
+            .server_addr()
+            .to_ip()
+            .ok_or("HTTP server is not bound to an IP address")?;
+        let listener = TcpListener::bind((config.bind_address, config.websocket_port))
+            .map_err(|e| format!("Unable to bind the WebSocket server: {e}"))?;
+        let websocket = listener
+            .local_addr()
+            .map_err(|e| format!("Unable to read the WebSocket server address: {e}"))?;
+
+        let http_server = Arc::new(http_server);
+        let (websocket_stop, stop) = crossbeam_channel::bounded(0);
+        let static_dir = config.static_dir.clone();
+        let http_handle = {
+            let http_server = http_server.clone();
+            thread::spawn(move || run_http_server(&http_server, &static_dir))
+        };
+        let websocket_handle =
+            thread::spawn(move || websocket::run_websocket_server(&listener, &stop));
+
+        let mut threads = THREADS.lock().unwrap();
+        threads.push(http_handle);
+        threads.push(websocket_handle);
+
+        let addresses = UiAddresses { http, websocket };
+        *ui_addresses = Some(addresses);
+        *ui_servers = Some(RunningUiServers {
+            config: config.clone(),
+            http_server,
+            websocket_stop,
+        });
+        Ok(addresses)
+    }
+
+    /// Registers the client with the web UI, starting its servers if needed
+    ///
+    /// ### Arguments
+    /// * `endpoint` - The UI side of the client, consumed by the web UI
+    ///
+    /// Returns whether the client was registered
+    pub(crate) fn run_ui(&self, endpoint: UiEndpoint) -> bool {
+        // Held until the client is registered, so that the servers are not
+        // stopped in between by the last client leaving
+        let mut ui_addresses = UI_ADDRESSES.lock().unwrap();
+        if let Err(err) = self.start_ui_servers_locked(&mut ui_addresses) {
+            error!("Client {}: Unable to start the UI: {}", self.id, err);
+            return false;
         }
 
         // add the client to the list
-        clients_state.insert(
+        CLIENTS_STATE.lock().unwrap().insert(
             self.id,
             ClientState {
                 known_nodes: Some(self.known_nodes.clone()),
-                sender: Some(sender),
-                receiver: Some(receiver),
+                topology: Some(self.shared_topology.clone()),
+                metrics: Some(self.shared_metrics.clone()),
+                history: self.history.clone(),
+                outbox: Some(self.shared_outbox.clone()),
+                events: Some(self.event_journal()),
+                sender: Some(endpoint.to_client),
             },
         );
+
+        // NodeId of the received messages is the source server
+        websocket::register_client(self.id, endpoint.from_client, endpoint.delivery_updates);
+        drop(ui_addresses);
+        true
     }
 }
 
-fn run_http_server() {
-    println!("[CLIENT-HTTP] Visit http://localhost:{HTTP_PORT} for the client UI");
-    let http_server = Server::http(format!("0.0.0.0:{HTTP_PORT}")).unwrap();
+/// Removes a client from the web UI, stopping its servers once no client is left
+///
+/// ### Arguments
+/// * `client_id` - The ID of the client
+pub(crate) fn unregister_client(client_id: NodeId) {
+    let mut ui_addresses = UI_ADDRESSES.lock().unwrap();
+    let mut clients = CLIENTS_STATE.lock().unwrap();
+    if clients.remove(&client_id).is_none() || !clients.is_empty() {
+        return;
+    }
+    drop(clients);
 
-    loop {
-        match http_server.try_recv() {
-            Ok(Some(request)) => {
-                if let Err(e) = request_handler::handle_request(request) {
-                    eprintln!("[CLIENT-HTTP] Error handling request: {e}");
-                }
-            }
-            Ok(None) => {
-                // No request available, sleep a bit
-                std::thread::sleep(std::time::Duration::from_millis(100));
-            }
-            Err(e) => {
-                eprintln!("[CLIENT-HTTP] Server error: {e}");
-                break;
-            }
-        }
+    let Some(servers) = UI_SERVERS.lock().unwrap().take() else {
+        return;
+    };
+    info!("[CLIENT-HTTP] No client left, stopping the UI servers");
+    servers.http_server.unblock();
+    if let Some(addresses) = ui_addresses.take() {
+        websocket::stop_websocket_server(servers.websocket_stop, addresses.websocket);
+    }
+    // The connections find their queue closed and close themselves
+    HUB.disconnect_all();
+}
+
+/// Serves the requests of the HTTP server, blocking until each one arrives
+///
+/// ### Arguments
+/// * `http_server` - The bound HTTP server
+/// * `static_dir` - Directory the assets of the frontend are served from
+fn run_http_server(http_server: &Server, static_dir: &Path) {
+    if let Some(addr) = http_server.server_addr().to_ip() {
+        info!("[CLIENT-HTTP] Visit http://{addr} for the client UI");
+    }
 
-        // Check if we should stop (no more clients)
-        let clients = CLIENTS_STATE.lock().unwrap();
-        if clients.is_empty() {
-            break;
+    for request in http_server.incoming_requests() {
+        if let Err(e) = request_handler::handle_request(request, static_dir) {
+            eprintln!("[CLIENT-HTTP] Error handling request: {e}");
         }
     }
 
@@ -1,16 +1,25 @@
 use crate::ui::api::get_clients::get_clients;
+use crate::ui::api::get_edge_stats::get_edge_stats;
+use crate::ui::api::get_events::get_events;
+use crate::ui::api::get_history::get_history;
+use crate::ui::api::get_metrics::get_client_metrics;
+use crate::ui::api::get_outbox::get_outbox;
+use crate::ui::api::get_prometheus_metrics::get_prometheus_metrics;
 use crate::ui::api::get_registered_users::get_registered_users;
 use crate::ui::api::get_servers::get_servers;
 use crate::ui::api::get_static_content::provide_static_file;
+use crate::ui::api::get_topology::get_topology;
+use crate::ui::api::get_ui_config::get_ui_config;
 use crate::ui::api::post_register::post_register;
 use crate::ui::api::post_send_message::post_send_message;
 use crate::ui::api::post_unregister::post_unregister;
 use log::info;
 use std::collections::HashMap;
 use std::io::Error;
+use std::path::Path;
 use tiny_http::{Method, Request, Response};
 
-pub(crate) fn handle_request(mut req: Request) -> Result<(), Error> {
+pub(crate) fn handle_request(mut req: Request, static_dir: &Path) -> Result<(), Error> {
     let method = req.method();
     let full_url = req.url(); // Include sia il path che i query parameters
     let path = full_url.split('?').next().unwrap_or("/"); // Ottieni solo il path
@@ -31,11 +40,19 @@ pub(crate) fn handle_request(mut req: Request) -> Result<(), Error> {
     info!("[CLIENT-HTTP] Received request: {method} {full_url}");
     let response = match (method, path) {
         // API GET
-        (Method::Get, "/") => provide_static_file("/index.html"),
+        (Method::Get, "/") => provide_static_file(static_dir, "/index.html"),
         (Method::Get, "/api/clients") => get_clients(),
         (Method::Get, "/api/servers") => get_servers(&query_params),
         (Method::Get, "/api/registered-users") => get_registered_users(&query_params),
-        (Method::Get, path) if path.starts_with('/') => provide_static_file(path),
+        (Method::Get, "/api/topology") => get_topology(&query_params),
+        (Method::Get, "/api/stats/edges") => get_edge_stats(&query_params),
+        (Method::Get, "/api/metrics") => get_client_metrics(&query_params),
+        (Method::Get, "/api/history") => get_history(&query_params),
+        (Method::Get, "/api/outbox") => get_outbox(&query_params),
+        (Method::Get, "/api/events") => get_events(&query_params),
+        (Method::Get, "/metrics") => get_prometheus_metrics(),
+        (Method::Get, "/api/ui-config") => get_ui_config(),
+        (Method::Get, path) if path.starts_with('/') => provide_static_file(static_dir, path),
 
         // API POST
         (Method::Post, "/api/send-to") => post_send_message(&mut req),
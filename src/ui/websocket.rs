@@ -1,103 +1,396 @@
-use crate::ui::{CLIENTS_STATE, HTTP_PORT, THREADS};
-use crossbeam_channel::TryRecvError;
+use crate::ui::api::get_edge_stats::clients_edge_stats;
+use crate::ui::hub::{Subscription, HUB};
+use crate::ui::ws_protocol::handle_ws_request;
+use crate::ui::THREADS;
+use crate::DeliveryUpdate;
+use common_utils::ServerToClientMessage;
+use crossbeam_channel::{select, tick, unbounded, Receiver, Select, Sender, TryRecvError};
+use lazy_static::lazy_static;
 use log::{error, info, warn};
-use std::net::{TcpListener, TcpStream};
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
 use std::thread;
-use std::thread::sleep;
 use std::time::Duration;
-use tungstenite::{Error, Message, WebSocket};
+use tungstenite::error::ProtocolError;
+use tungstenite::{Error, HandshakeError, Message, WebSocket};
+use wg_2024::network::NodeId;
 
-const WEBSOCKET_PORT: u16 = HTTP_PORT + 1;
+/// How often the edge statistics of every client are pushed to each connection
+const EDGE_STATS_PUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The channels on which a client sends its messages and the delivery
+/// status of the messages sent by the UI
+struct ClientReceiver {
+    client_id: NodeId,
+    messages: Receiver<(NodeId, ServerToClientMessage)>,
+    delivery_updates: Receiver<DeliveryUpdate>,
+}
+
+lazy_static! {
+    // Receivers of the clients started after the dispatcher
+    static ref REGISTRATIONS: (Sender<ClientReceiver>, Receiver<ClientReceiver>) = unbounded();
+}
+
+/// Hands the messages a client sends to the UI over to the dispatcher
+///
+/// ### Arguments
+/// * `client_id` - The ID of the client
+/// * `messages` - Channel on which the client sends its messages for the UI
+/// * `delivery_updates` - Channel on which the client sends the delivery status
+///   of the messages sent by the UI
+pub(crate) fn register_client(
+    client_id: NodeId,
+    messages: Receiver<(NodeId, ServerToClientMessage)>,
+    delivery_updates: Receiver<DeliveryUpdate>,
+) {
+    REGISTRATIONS
+        .0
+        .send(ClientReceiver {
+            client_id,
+            messages,
+            delivery_updates,
+        })
+        .ok();
+}
 
 /// Runs the WebSocket server that handles client connections
-/// and message distribution
-pub(crate) fn run_websocket_server() {
-    let listener = TcpListener::bind(format!("0.0.0.0:{WEBSOCKET_PORT}")).unwrap();
-    listener.set_nonblocking(true).unwrap();
+/// and message distribution, until `stop` is disconnected
+///
+/// ### Arguments
+/// * `listener` - The bound listener of the WebSocket server
+/// * `stop` - Channel whose sender is dropped to stop the server
+pub(crate) fn run_websocket_server(listener: &TcpListener, stop: &Receiver<()>) {
+    let dispatcher_stop = stop.clone();
+    THREADS.lock().unwrap().push(thread::spawn(move || {
+        dispatch_client_messages(&REGISTRATIONS.1, &dispatcher_stop);
+    }));
 
-    loop {
-        match listener.accept() {
-            Ok((ws_stream, _)) => {
+    for ws_stream in listener.incoming() {
+        // The connection that woke the listener up is not served
+        if let Err(TryRecvError::Disconnected) = stop.try_recv() {
+            break;
+        }
+        match ws_stream {
+            Ok(ws_stream) => {
                 let web_socket_updates = thread::spawn(move || {
-                    if let Ok(web_socket_stream) = tungstenite::accept(ws_stream) {
-                        if let Err(e) = handle_new_connection(web_socket_stream) {
-                            error!("[CLIENT-WS] Connection error: {}", e);
-                        }
+                    if let Err(e) = handle_new_connection(ws_stream) {
+                        error!("[CLIENT-WS] Connection error: {}", e);
                     }
                 });
                 THREADS.lock().unwrap().push(web_socket_updates);
             }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                // No new connections, continue
-                sleep(Duration::from_millis(100));
-            }
             Err(e) => {
                 error!("[CLIENT-WS] Failed to accept connection: {}", e);
                 break;
             }
         }
+    }
+
+    info!("[CLIENT-WS] WebSocket server shutting down");
+}
+
+/// Stops the WebSocket server and its dispatcher
+///
+/// ### Arguments
+/// * `stop` - The sender the server watches
+/// * `address` - The address the server is bound to
+pub(crate) fn stop_websocket_server(stop: Sender<()>, address: SocketAddr) {
+    drop(stop);
+    // Wakes the listener up, blocked waiting for a connection
+    let _ = TcpStream::connect(address);
+}
+
+/// Builds the message carrying the edge statistics of a client
+fn edge_stats_message(client_id: NodeId, edge_stats: &impl serde::Serialize) -> String {
+    serde_json::json!({
+        "type": "edge_stats",
+        "client_id": client_id,
+        "edge_stats": edge_stats
+    })
+    .to_string()
+}
 
-        let clients = CLIENTS_STATE.lock().unwrap();
-        if clients.is_empty() {
+/// Builds the message carrying the new delivery status of a message
+fn delivery_status_message(client_id: NodeId, update: &DeliveryUpdate) -> String {
+    serde_json::json!({
+        "type": "delivery_status",
+        "client_id": client_id,
+        "message_id": update.message_id,
+        "destination": update.destination,
+        "status": update.status
+    })
+    .to_string()
+}
+
+/// Moves the messages and delivery updates of every client to the broadcast
+/// hub as soon as they arrive, and periodically broadcasts the edge statistics.
+/// The hub keeps the messages for the next connection while none is open.
+///
+/// Blocks until one of the clients, a new registration, the edge
+/// statistics timer or the stop signal is ready.
+///
+/// ### Arguments
+/// * `registrations` - Channel on which the receivers of new clients arrive
+/// * `stop` - Channel whose sender is dropped to stop the dispatcher
+fn dispatch_client_messages(registrations: &Receiver<ClientReceiver>, stop: &Receiver<()>) {
+    let edge_stats_ticker = tick(EDGE_STATS_PUSH_INTERVAL);
+    let mut clients: Vec<ClientReceiver> = Vec::new();
+
+    loop {
+        let mut select = Select::new();
+        let stop_index = select.recv(stop);
+        let registrations_index = select.recv(registrations);
+        let ticker_index = select.recv(&edge_stats_ticker);
+        let client_indexes: Vec<(usize, usize)> = clients
+            .iter()
+            .map(|client| {
+                (
+                    select.recv(&client.messages),
+                    select.recv(&client.delivery_updates),
+                )
+            })
+            .collect();
+
+        let operation = select.select();
+        let index = operation.index();
+
+        if index == stop_index {
+            let _ = operation.recv(stop);
             break;
+        } else if index == registrations_index {
+            match operation.recv(registrations) {
+                Ok(client) => clients.push(client),
+                Err(_) => break,
+            }
+        } else if index == ticker_index {
+            let _ = operation.recv(&edge_stats_ticker);
+            for (client_id, edge_stats) in clients_edge_stats() {
+                HUB.broadcast(client_id, &edge_stats_message(client_id, &edge_stats));
+            }
+        } else if let Some(position) = client_indexes.iter().position(|(i, _)| *i == index) {
+            let client_id = clients[position].client_id;
+            match operation.recv(&clients[position].messages) {
+                Ok((server_id, message)) => {
+                    let ws_message = serde_json::json!({
+                        "type": "message",
+                        "client_id": client_id,
+                        "server_id": server_id,
+                        "message": message
+                    })
+                    .to_string();
+                    HUB.broadcast_or_keep(client_id, &ws_message);
+                }
+                Err(_) => {
+                    warn!("[CLIENT-WS] Channel disconnected for client {}", client_id);
+                    clients.remove(position);
+                }
+            }
+        } else if let Some(position) = client_indexes.iter().position(|(_, i)| *i == index) {
+            let client_id = clients[position].client_id;
+            match operation.recv(&clients[position].delivery_updates) {
+                Ok(update) => {
+                    HUB.broadcast_or_keep(client_id, &delivery_status_message(client_id, &update));
+                }
+                Err(_) => {
+                    warn!("[CLIENT-WS] Channel disconnected for client {}", client_id);
+                    clients.remove(position);
+                }
+            }
         }
     }
+}
 
-    info!("[CLIENT-WS] WebSocket server shutting down");
+/// The socket of a connection as seen by its WebSocket: reads come from the
+/// bytes the reader thread received, writes go straight to the socket
+struct ConnectionStream {
+    socket: TcpStream,
+    received: VecDeque<u8>,
+    /// The reader thread reached the end of the stream
+    closed: bool,
+}
+
+impl Read for ConnectionStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.received.is_empty() {
+            return if self.closed {
+                Ok(0)
+            } else {
+                Err(ErrorKind::WouldBlock.into())
+            };
+        }
+        self.received.read(buf)
+    }
 }
 
-/// Handles a new WebSocket connection, managing message forwarding
-/// between clients
+impl Write for ConnectionStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.socket.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.socket.flush()
+    }
+}
+
+/// Reads the socket of a connection until it is closed, forwarding what
+/// arrives. Only reads: every frame is written by the connection thread.
 ///
 /// ### Arguments
-/// * `ws_stream` - The WebSocket stream for the new connection
-fn handle_new_connection(
-    mut ws_stream: WebSocket<TcpStream>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    info!("[CLIENT-WS] New WebSocket connection");
-    ws_stream.get_ref().set_nonblocking(true)?;
-
+/// * `socket` - The socket of the connection
+/// * `received` - Channel on which the bytes read are forwarded
+fn read_connection(mut socket: TcpStream, received: &Sender<Vec<u8>>) {
+    let mut buffer = [0; 4096];
     loop {
-        let clients = CLIENTS_STATE.lock().map_err(|_| "Failed to acquire lock")?;
-        for (client_id, client_state) in &*clients {
-            if let Some(receiver) = &client_state.receiver {
-                match receiver.try_recv() {
-                    Ok(msg) => {
-                        let ws_message = serde_json::json!({
-                            "client_id": client_id,
-                            "server_id": msg.0,
-                            "message": msg.1
-                        })
-                        .to_string();
-
-                        ws_stream
-                            .send(Message::Text(ws_message.into()))
-                            .map_err(|e| {
-                                warn!("[CLIENT-WS] Failed to send message: {e:?}");
-                                e
-                            })?;
-                    }
-                    Err(TryRecvError::Empty) => { /* CONTINUE */ }
-                    Err(TryRecvError::Disconnected) => {
-                        warn!("[CLIENT-WS] Channel disconnected for client {}", client_id);
-                    }
+        match socket.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if received.send(buffer[..n].to_vec()).is_err() {
+                    break;
                 }
             }
         }
-        drop(clients);
+    }
+}
 
+/// Executes the requests whose bytes arrived so far
+///
+/// ### Arguments
+/// * `ws_stream` - The connection
+/// * `subscription` - The hub subscription of the connection
+///
+/// ### Returns
+/// Whether the connection is still open
+fn handle_pending_requests(
+    ws_stream: &mut WebSocket<ConnectionStream>,
+    subscription: &Subscription,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    loop {
         match ws_stream.read() {
-            Err(Error::ConnectionClosed | Error::AlreadyClosed) => break,
-            Err(Error::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                sleep(Duration::from_millis(50));
-            }
-            Err(e) => {
-                warn!("[CLIENT-WS] WebSocket error: {}", e);
+            Ok(Message::Text(request)) => {
+                let reply = handle_ws_request(&request, subscription);
+                ws_stream.send(Message::Text(serde_json::to_string(&reply)?.into()))?;
             }
+            // Pings are answered by tungstenite before the next read
             Ok(_) => continue,
+            Err(Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => return Ok(true),
+            Err(
+                Error::ConnectionClosed
+                | Error::AlreadyClosed
+                | Error::Protocol(ProtocolError::ResetWithoutClosingHandshake),
+            ) => return Ok(false),
+            Err(e) => return Err(e.into()),
         }
     }
+}
 
+/// Completes the handshake of a connection with the bytes the reader thread
+/// forwards
+///
+/// ### Arguments
+/// * `stream` - The connection, before the handshake
+/// * `received` - Channel on which the reader thread forwards the bytes read
+fn accept_connection(
+    stream: ConnectionStream,
+    received: &Receiver<Vec<u8>>,
+) -> Result<WebSocket<ConnectionStream>, Box<dyn std::error::Error>> {
+    let mut handshake = tungstenite::accept(stream);
+    loop {
+        match handshake {
+            Ok(ws_stream) => return Ok(ws_stream),
+            Err(HandshakeError::Interrupted(mut mid_handshake)) => {
+                let stream = mid_handshake.get_mut().get_mut();
+                match received.recv() {
+                    Ok(bytes) => stream.received.extend(bytes),
+                    Err(_) => stream.closed = true,
+                }
+                handshake = mid_handshake.handshake();
+            }
+            Err(HandshakeError::Failure(e)) => return Err(e.into()),
+        }
+    }
+}
+
+/// Handles a new WebSocket connection, forwarding to it the messages
+/// broadcast by the hub and executing the requests it sends.
+///
+/// A reader thread blocks on the socket and hands the bytes over, so that the
+/// connection thread blocks until a request or a broadcast message arrives.
+///
+/// ### Arguments
+/// * `socket` - The socket of the new connection
+fn handle_new_connection(socket: TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+    let (received_sender, received) = unbounded();
+    let reader_socket = socket.try_clone()?;
+    let reader = {
+        let reader_socket = reader_socket.try_clone()?;
+        thread::spawn(move || read_connection(reader_socket, &received_sender))
+    };
+
+    let stream = ConnectionStream {
+        socket,
+        received: VecDeque::new(),
+        closed: false,
+    };
+    let result = accept_connection(stream, &received)
+        .and_then(|mut ws_stream| serve_connection(&mut ws_stream, &received));
+
+    // Unblocks the reader thread
+    let _ = reader_socket.shutdown(Shutdown::Both);
+    let _ = reader.join();
     info!("[CLIENT-WS] WebSocket connection closed");
+    result
+}
+
+/// Serves a connection until it is closed
+///
+/// ### Arguments
+/// * `ws_stream` - The connection
+/// * `received` - Channel on which the reader thread forwards the bytes read
+fn serve_connection(
+    ws_stream: &mut WebSocket<ConnectionStream>,
+    received: &Receiver<Vec<u8>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let subscription = HUB.subscribe();
+    info!(
+        "[CLIENT-WS] New WebSocket connection, {} connected",
+        HUB.connection_count()
+    );
+
+    // New connections get the edge statistics without waiting for the next push
+    for (client_id, edge_stats) in clients_edge_stats() {
+        let message = edge_stats_message(client_id, &edge_stats);
+        ws_stream.send(Message::Text(message.into())).map_err(|e| {
+            warn!("[CLIENT-WS] Failed to send edge stats: {e:?}");
+            e
+        })?;
+    }
+
+    loop {
+        select! {
+            recv(received) -> bytes => {
+                match bytes {
+                    Ok(bytes) => ws_stream.get_mut().received.extend(bytes),
+                    Err(_) => ws_stream.get_mut().closed = true,
+                }
+                if !handle_pending_requests(ws_stream, &subscription)? {
+                    break;
+                }
+            }
+            recv(subscription.receiver) -> ws_message => {
+                let Ok(ws_message) = ws_message else {
+                    // Removed by the hub for being too slow
+                    info!("[CLIENT-WS] Connection {} dropped", subscription.id);
+                    let _ = ws_stream.close(None);
+                    let _ = ws_stream.flush();
+                    break;
+                };
+                if let Err(e) = ws_stream.send(Message::Text(ws_message.into())) {
+                    warn!("[CLIENT-WS] Failed to send message: {e:?}");
+                    return Err(e.into());
+                }
+            }
+        }
+    }
     Ok(())
 }
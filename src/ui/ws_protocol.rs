@@ -0,0 +1,193 @@
+use crate::ui::hub::Subscription;
+use crate::ui::CLIENTS_STATE;
+use common_utils::{ClientToServerMessage, MessageBody, MessageContent};
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// Requests a browser can send over the WebSocket, tagged by `op`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum WsRequest {
+    Subscribe {
+        request_id: u64,
+        client_ids: Vec<NodeId>,
+    },
+    Unsubscribe {
+        request_id: u64,
+        client_ids: Vec<NodeId>,
+    },
+    SendMessage {
+        request_id: u64,
+        client_id: NodeId,
+        server_id: NodeId,
+        recipient_id: NodeId,
+        content: MessageContent,
+        // defaults to the current time
+        timestamp: Option<String>,
+    },
+    Register {
+        request_id: u64,
+        client_id: NodeId,
+        server_id: NodeId,
+        username: String,
+    },
+    Unregister {
+        request_id: u64,
+        client_id: NodeId,
+        server_id: NodeId,
+    },
+    RequestActiveUsers {
+        request_id: u64,
+        client_id: NodeId,
+        server_id: NodeId,
+    },
+}
+
+/// Replies to a request, tagged by `type`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum WsReply {
+    Ack {
+        request_id: u64,
+        // set when a message was handed to the client, its delivery status
+        // is then pushed with this ID
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message_id: Option<u64>,
+    },
+    Error {
+        // missing when the request could not be parsed
+        request_id: Option<u64>,
+        error: String,
+    },
+}
+
+impl WsRequest {
+    fn request_id(&self) -> u64 {
+        match self {
+            WsRequest::Subscribe { request_id, .. }
+            | WsRequest::Unsubscribe { request_id, .. }
+            | WsRequest::SendMessage { request_id, .. }
+            | WsRequest::Register { request_id, .. }
+            | WsRequest::Unregister { request_id, .. }
+            | WsRequest::RequestActiveUsers { request_id, .. } => *request_id,
+        }
+    }
+}
+
+/// Executes a request received on a WebSocket connection
+///
+/// ### Arguments
+/// * `text` - The JSON text of the request
+/// * `subscription` - The hub subscription of the connection
+///
+/// Returns the reply to send back on the connection
+pub(crate) fn handle_ws_request(text: &str, subscription: &Subscription) -> WsReply {
+    let request: WsRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(err) => {
+            return WsReply::Error {
+                request_id: serde_json::from_str::<serde_json::Value>(text)
+                    .ok()
+                    .and_then(|json| json["request_id"].as_u64()),
+                error: format!("Invalid request: {err}"),
+            }
+        }
+    };
+    let request_id = request.request_id();
+
+    let result = match request {
+        WsRequest::Subscribe { client_ids, .. } => {
+            subscription.subscribe_clients(&client_ids);
+            Ok(None)
+        }
+        WsRequest::Unsubscribe { client_ids, .. } => {
+            subscription.unsubscribe_clients(&client_ids);
+            Ok(None)
+        }
+        WsRequest::SendMessage {
+            client_id,
+            server_id,
+            recipient_id,
+            content,
+            timestamp,
+            ..
+        } => forward_to_client(
+            client_id,
+            server_id,
+            ClientToServerMessage::SendPrivateMessage {
+                recipient_id,
+                message: MessageBody {
+                    sender_id: client_id,
+                    content,
+                    timestamp: timestamp
+                        .unwrap_or_else(|| chrono::Local::now().format("%H:%M").to_string()),
+                },
+            },
+        )
+        .map(Some),
+        WsRequest::Register {
+            client_id,
+            server_id,
+            username,
+            ..
+        } => forward_to_client(
+            client_id,
+            server_id,
+            ClientToServerMessage::RegisterUser { name: username },
+        )
+        .map(Some),
+        WsRequest::Unregister {
+            client_id,
+            server_id,
+            ..
+        } => {
+            forward_to_client(client_id, server_id, ClientToServerMessage::UnregisterUser).map(Some)
+        }
+        WsRequest::RequestActiveUsers {
+            client_id,
+            server_id,
+            ..
+        } => forward_to_client(
+            client_id,
+            server_id,
+            ClientToServerMessage::RequestActiveUsers,
+        )
+        .map(Some),
+    };
+
+    match result {
+        Ok(message_id) => WsReply::Ack {
+            request_id,
+            message_id,
+        },
+        Err(error) => WsReply::Error {
+            request_id: Some(request_id),
+            error,
+        },
+    }
+}
+
+/// Hands a message to a client node, to be sent to a server
+///
+/// ### Arguments
+/// * `client_id` - The client sending the message
+/// * `server_id` - The destination server
+/// * `message` - The message to send
+///
+/// Returns the ID assigned to the message
+fn forward_to_client(
+    client_id: NodeId,
+    server_id: NodeId,
+    message: ClientToServerMessage,
+) -> Result<u64, String> {
+    let sender = CLIENTS_STATE
+        .lock()
+        .unwrap()
+        .get(&client_id)
+        .and_then(|client| client.sender.clone())
+        .ok_or_else(|| format!("Unknown client {client_id}"))?;
+
+    sender
+        .send(server_id, message)
+        .ok_or_else(|| format!("Client {client_id} is not running"))
+}